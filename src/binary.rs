@@ -0,0 +1,581 @@
+//! Self-describing container format for `graph.bin`: an 8-byte magic, a
+//! format version, a flags byte, a codec byte, and three length-prefixed
+//! sections (nodes, edges, periods), each optionally followed by a checksum
+//! of its own bytes so a reader can validate the file before trusting it.
+//! When the compressed flag is set, the codec byte names the encoding
+//! (currently only deflate) and the sections are deflated as a single block
+//! rather than stored as plain bytes.
+
+use std::io::{Read, Write};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::graph::{Edge, Graph, Journey, Node, OperatingPeriod, Timetable};
+
+const MAGIC: &[u8; 8] = b"NETEXGRF";
+// Bumped from 1 because this version always writes a codec byte after the
+// flags byte, which older readers don't expect.
+const VERSION: u32 = 2;
+const FLAG_CHECKSUMS: u8 = 0b0000_0001;
+const FLAG_PACKED_JOURNEYS: u8 = 0b0000_0010;
+const FLAG_COMPRESSED: u8 = 0b0000_0100;
+
+const CODEC_NONE: u8 = 0;
+const CODEC_DEFLATE: u8 = 1;
+
+fn write_uvarint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// Zig-zag encodes a signed delta so small negative values (the ordinary
+// same-day case, departure < arrival) stay small as a varint instead of
+// sign-extending to a huge unsigned value. A connection that wraps past
+// midnight (arrival < departure, same convention as `filter_journeys` and
+// `Graph::earliest_arrival`) yields a positive delta here, which zig-zag
+// encoding handles just as compactly.
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+#[derive(Debug)]
+pub enum BinaryError {
+    BadMagic,
+    UnsupportedVersion(u32),
+    UnsupportedCodec(u8),
+    Truncated,
+    ChecksumMismatch { section: &'static str },
+    Decompression(std::io::Error),
+}
+
+impl std::fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinaryError::BadMagic => write!(f, "not a netex-parse graph.bin file (bad magic)"),
+            BinaryError::UnsupportedVersion(version) => {
+                write!(f, "unsupported graph.bin format version {version}")
+            }
+            BinaryError::UnsupportedCodec(codec) => {
+                write!(f, "unsupported graph.bin compression codec {codec}")
+            }
+            BinaryError::Truncated => write!(f, "graph.bin file is truncated"),
+            BinaryError::ChecksumMismatch { section } => {
+                write!(f, "checksum mismatch in {section} section")
+            }
+            BinaryError::Decompression(err) => write!(f, "failed to inflate graph.bin: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BinaryError::Decompression(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    xxh3_64(bytes) as u32
+}
+
+fn node_as_bytes(node: &Node) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&node.id.to_le_bytes());
+    data.extend_from_slice(&node.lat.to_le_bytes());
+    data.extend_from_slice(&node.long.to_le_bytes());
+    let name_bytes = node.short_name.as_bytes();
+    data.extend_from_slice(&(name_bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(name_bytes);
+    data
+}
+
+fn period_as_bytes(period: &OperatingPeriod) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&period.from.to_le_bytes());
+    data.extend_from_slice(&period.to.to_le_bytes());
+    data.extend_from_slice(&(period.valid_day.len() as u32).to_le_bytes());
+    data.extend_from_slice(&period.valid_day);
+    data
+}
+
+// Sorts `journeys` by arrival and writes them as a base arrival plus varint
+// deltas: each subsequent arrival as a non-negative delta from the previous
+// one, each departure as a zig-zag delta from its own arrival (positive when
+// the connection wraps past midnight, i.e. arrival < departure, same
+// convention as `Graph::earliest_arrival`), and the operating-period index
+// as a varint.
+fn write_packed_journeys(out: &mut Vec<u8>, journeys: &[Journey]) {
+    write_uvarint(out, journeys.len() as u64);
+    let mut sorted: Vec<&Journey> = journeys.iter().collect();
+    sorted.sort_by_key(|journey| journey.arrival);
+    let mut prev_arrival: u16 = 0;
+    for journey in sorted {
+        write_uvarint(out, u64::from(journey.arrival - prev_arrival));
+        prev_arrival = journey.arrival;
+        write_uvarint(
+            out,
+            zigzag_encode(i64::from(journey.departure) - i64::from(journey.arrival)),
+        );
+        write_uvarint(out, journey.operating_period as u64);
+    }
+}
+
+// Writes a length-prefixed block plus, when `skip_checksum` is false, a
+// trailing checksum of exactly the bytes just written.
+fn write_section(
+    writer: &mut impl Write,
+    block: &[u8],
+    skip_checksum: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    writer.write_all(&(block.len() as u32).to_le_bytes())?;
+    writer.write_all(block)?;
+    if !skip_checksum {
+        writer.write_all(&checksum(block).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Encodes `graph` into the self-describing binary container. Checksums are
+/// written per section unless `skip_checksums` is set, mirroring a "skip
+/// checksum" mode for users who want the smallest possible file. When
+/// `pack_journeys` is set, each edge's journeys are sorted by arrival and
+/// stored as a base arrival plus varint deltas instead of fixed 6-byte
+/// records, which roughly halves the journey payload for dense timetables.
+/// When `compress` is set, the three sections are written to a deflate
+/// stream instead of straight into the output, shrinking the mostly
+/// repetitive integer payload at the cost of decode-time inflation.
+pub fn encode(
+    graph: &Graph,
+    skip_checksums: bool,
+    pack_journeys: bool,
+    compress: bool,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    let mut flags = if skip_checksums { 0 } else { FLAG_CHECKSUMS };
+    if pack_journeys {
+        flags |= FLAG_PACKED_JOURNEYS;
+    }
+    if compress {
+        flags |= FLAG_COMPRESSED;
+    }
+    out.push(flags);
+    out.push(if compress { CODEC_DEFLATE } else { CODEC_NONE });
+
+    let mut body = Vec::new();
+
+    let mut node_block = Vec::new();
+    node_block.extend_from_slice(&(graph.nodes.len() as u32).to_le_bytes());
+    for node in &graph.nodes {
+        node_block.extend_from_slice(&node_as_bytes(node));
+    }
+    write_section(&mut body, &node_block, skip_checksums)?;
+
+    let mut edge_block = Vec::new();
+    edge_block.extend_from_slice(&(graph.edges.len() as u32).to_le_bytes());
+    for edge in &graph.edges {
+        edge_block.extend_from_slice(&(edge.start_node as u32).to_le_bytes());
+        edge_block.extend_from_slice(&(edge.end_node as u32).to_le_bytes());
+        edge_block.extend_from_slice(&edge.walk_seconds.to_le_bytes());
+        if pack_journeys {
+            write_packed_journeys(&mut edge_block, &edge.timetable.journeys);
+        } else {
+            edge_block.extend_from_slice(&(edge.timetable.journeys.len() as u32).to_le_bytes());
+            for journey in &edge.timetable.journeys {
+                edge_block.extend_from_slice(&journey.arrival.to_le_bytes());
+                edge_block.extend_from_slice(&journey.departure.to_le_bytes());
+                edge_block.extend_from_slice(&(journey.operating_period as u16).to_le_bytes());
+            }
+        }
+        edge_block.extend_from_slice(&(edge.timetable.periods.len() as u32).to_le_bytes());
+    }
+    write_section(&mut body, &edge_block, skip_checksums)?;
+
+    let mut period_block = Vec::new();
+    let total_periods: usize = graph.edges.iter().map(|e| e.timetable.periods.len()).sum();
+    period_block.extend_from_slice(&(total_periods as u32).to_le_bytes());
+    for edge in &graph.edges {
+        for period in &edge.timetable.periods {
+            period_block.extend_from_slice(&period_as_bytes(period));
+        }
+    }
+    write_section(&mut body, &period_block, skip_checksums)?;
+
+    if compress {
+        let mut encoder = DeflateEncoder::new(out, Compression::default());
+        encoder.write_all(&body)?;
+        out = encoder.finish()?;
+    } else {
+        out.extend_from_slice(&body);
+    }
+
+    Ok(out)
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(bytes: &'a [u8]) -> Cursor<'a> {
+        Cursor { bytes, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BinaryError> {
+        let end = self.pos.checked_add(len).ok_or(BinaryError::Truncated)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(BinaryError::Truncated)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, BinaryError> {
+        Ok(self.take(1)?[0])
+    }
+
+    // Everything from the current position to the end of the buffer,
+    // i.e. the part of the container not yet parsed.
+    fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.pos..]
+    }
+
+    fn u16(&mut self) -> Result<u16, BinaryError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn uvarint(&mut self) -> Result<u64, BinaryError> {
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+            if shift >= 64 {
+                return Err(BinaryError::Truncated);
+            }
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, BinaryError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, BinaryError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, BinaryError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    // Reads a length-prefixed section, verifying its trailing checksum (when
+    // present) before returning its payload.
+    fn section(
+        &mut self,
+        name: &'static str,
+        has_checksum: bool,
+    ) -> Result<Cursor<'a>, BinaryError> {
+        let len = self.u32()? as usize;
+        let block = self.take(len)?;
+        if has_checksum {
+            let expected = self.u32()?;
+            if checksum(block) != expected {
+                return Err(BinaryError::ChecksumMismatch { section: name });
+            }
+        }
+        Ok(Cursor::new(block))
+    }
+}
+
+fn read_journeys(section: &mut Cursor) -> Result<Vec<Journey>, BinaryError> {
+    let journey_count = section.u32()? as usize;
+    let mut journeys = Vec::with_capacity(journey_count);
+    for _ in 0..journey_count {
+        let arrival = section.u16()?;
+        let departure = section.u16()?;
+        let operating_period = section.u16()? as usize;
+        journeys.push(Journey {
+            arrival,
+            departure,
+            operating_period,
+            transport_mode: String::new(),
+            line: String::new(),
+            controller: String::new(),
+        });
+    }
+    Ok(journeys)
+}
+
+// Inverse of `write_packed_journeys`: reads a base arrival plus varint
+// deltas, reconstructing absolute arrival/departure times.
+fn read_packed_journeys(section: &mut Cursor) -> Result<Vec<Journey>, BinaryError> {
+    let journey_count = section.uvarint()? as usize;
+    let mut journeys = Vec::with_capacity(journey_count);
+    let mut prev_arrival: u16 = 0;
+    for _ in 0..journey_count {
+        let arrival_delta = section.uvarint()?;
+        let arrival = prev_arrival
+            .checked_add(u16::try_from(arrival_delta).map_err(|_| BinaryError::Truncated)?)
+            .ok_or(BinaryError::Truncated)?;
+        prev_arrival = arrival;
+        let departure_delta = zigzag_decode(section.uvarint()?);
+        let departure = i64::from(arrival) + departure_delta;
+        let departure = u16::try_from(departure).map_err(|_| BinaryError::Truncated)?;
+        let operating_period = section.uvarint()? as usize;
+        journeys.push(Journey {
+            arrival,
+            departure,
+            operating_period,
+            transport_mode: String::new(),
+            line: String::new(),
+            controller: String::new(),
+        });
+    }
+    Ok(journeys)
+}
+
+/// Decodes a `graph.bin` container written by `encode`, verifying the magic,
+/// version, and any per-section checksums before reconstructing the `Graph`.
+pub fn decode(bytes: &[u8]) -> Result<Graph, BinaryError> {
+    let mut cursor = Cursor::new(bytes);
+    if cursor.take(MAGIC.len())? != MAGIC.as_slice() {
+        return Err(BinaryError::BadMagic);
+    }
+    let version = cursor.u32()?;
+    if version != VERSION {
+        return Err(BinaryError::UnsupportedVersion(version));
+    }
+    let flags = cursor.u8()?;
+    let codec = cursor.u8()?;
+    let has_checksums = flags & FLAG_CHECKSUMS != 0;
+    let packed_journeys = flags & FLAG_PACKED_JOURNEYS != 0;
+    let compressed = flags & FLAG_COMPRESSED != 0;
+
+    let inflated;
+    let mut cursor = if compressed {
+        match codec {
+            CODEC_DEFLATE => {
+                let mut buf = Vec::new();
+                DeflateDecoder::new(cursor.remaining())
+                    .read_to_end(&mut buf)
+                    .map_err(BinaryError::Decompression)?;
+                inflated = buf;
+                Cursor::new(&inflated)
+            }
+            other => return Err(BinaryError::UnsupportedCodec(other)),
+        }
+    } else {
+        cursor
+    };
+
+    let mut nodes_section = cursor.section("nodes", has_checksums)?;
+    let node_count = nodes_section.u32()? as usize;
+    let mut nodes = Vec::with_capacity(node_count);
+    for _ in 0..node_count {
+        let id = nodes_section.u64()?;
+        let lat = nodes_section.f32()?;
+        let long = nodes_section.f32()?;
+        let name_len = nodes_section.u32()? as usize;
+        let name_bytes = nodes_section.take(name_len)?;
+        nodes.push(Node {
+            id,
+            short_name: String::from_utf8_lossy(name_bytes).into_owned(),
+            long,
+            lat,
+        });
+    }
+
+    let mut edges_section = cursor.section("edges", has_checksums)?;
+    let edge_count = edges_section.u32()? as usize;
+    let mut edges = Vec::with_capacity(edge_count);
+    let mut period_counts = Vec::with_capacity(edge_count);
+    for _ in 0..edge_count {
+        let start_node = edges_section.u32()? as usize;
+        let end_node = edges_section.u32()? as usize;
+        let walk_seconds = edges_section.u16()?;
+        let journeys = if packed_journeys {
+            read_packed_journeys(&mut edges_section)?
+        } else {
+            read_journeys(&mut edges_section)?
+        };
+        let period_count = edges_section.u32()? as usize;
+        period_counts.push(period_count);
+        edges.push(Edge {
+            start_node,
+            end_node,
+            walk_seconds,
+            timetable: Timetable {
+                journeys,
+                periods: Vec::new(),
+            },
+        });
+    }
+
+    let mut periods_section = cursor.section("periods", has_checksums)?;
+    let _total_periods = periods_section.u32()?;
+    for (edge, period_count) in edges.iter_mut().zip(period_counts) {
+        for _ in 0..period_count {
+            let from = periods_section.u32()?;
+            let to = periods_section.u32()?;
+            let valid_day_len = periods_section.u32()? as usize;
+            let valid_day = periods_section.take(valid_day_len)?.to_vec();
+            edge.timetable.periods.push(OperatingPeriod {
+                from,
+                to,
+                valid_day_bits: base64::encode(&valid_day),
+                valid_day,
+            });
+        }
+    }
+
+    Ok(Graph::new(nodes, edges))
+}
+
+impl Graph {
+    /// Decodes a `graph.bin` container written by `binary::encode`.
+    pub fn from_binary(bytes: &[u8]) -> Result<Graph, BinaryError> {
+        decode(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Node;
+
+    fn sample_graph() -> Graph {
+        let nodes = vec![
+            Node {
+                id: 1,
+                short_name: "A".to_owned(),
+                long: 1.0,
+                lat: 2.0,
+            },
+            Node {
+                id: 2,
+                short_name: "B".to_owned(),
+                long: 3.0,
+                lat: 4.0,
+            },
+        ];
+        let edges = vec![Edge {
+            start_node: 0,
+            end_node: 1,
+            walk_seconds: 120,
+            timetable: Timetable {
+                journeys: vec![Journey {
+                    departure: 480,
+                    arrival: 510,
+                    transport_mode: "bus".to_owned(),
+                    operating_period: 0,
+                    line: "1".to_owned(),
+                    controller: "Acme".to_owned(),
+                }],
+                periods: vec![OperatingPeriod {
+                    from: 220_101,
+                    to: 220_131,
+                    valid_day_bits: String::new(),
+                    valid_day: vec![0b0101_0101],
+                }],
+            },
+        }];
+        Graph::new(nodes, edges)
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        for (skip_checksums, pack_journeys, compress) in
+            [(false, false, false), (true, true, false), (false, true, true)]
+        {
+            let graph = sample_graph();
+            let bytes = encode(&graph, skip_checksums, pack_journeys, compress).unwrap();
+            let decoded = decode(&bytes).unwrap();
+
+            assert_eq!(decoded.nodes.len(), 2);
+            assert_eq!(decoded.nodes[0].id, 1);
+            assert_eq!(decoded.nodes[1].short_name, "B");
+
+            assert_eq!(decoded.edges.len(), 1);
+            assert_eq!(decoded.edges[0].walk_seconds, 120);
+            assert_eq!(decoded.edges[0].timetable.journeys.len(), 1);
+            assert_eq!(decoded.edges[0].timetable.journeys[0].arrival, 510);
+            assert_eq!(decoded.edges[0].timetable.journeys[0].departure, 480);
+            assert_eq!(decoded.edges[0].timetable.periods.len(), 1);
+            assert_eq!(decoded.edges[0].timetable.periods[0].from, 220_101);
+            assert_eq!(
+                decoded.edges[0].timetable.periods[0].valid_day,
+                vec![0b0101_0101]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_rejects_bad_magic() {
+        assert!(matches!(decode(b"not a graph"), Err(BinaryError::BadMagic)));
+    }
+
+    #[test]
+    fn packed_journeys_round_trip_chained_deltas_and_midnight_wraparound() {
+        let journeys = vec![
+            Journey {
+                departure: 480,
+                arrival: 510,
+                transport_mode: String::new(),
+                operating_period: 0,
+                line: String::new(),
+                controller: String::new(),
+            },
+            Journey {
+                departure: 600,
+                arrival: 630,
+                transport_mode: String::new(),
+                operating_period: 1,
+                line: String::new(),
+                controller: String::new(),
+            },
+            // Departs at 23:50 and arrives at 00:10 the next day: arrival <
+            // departure, the midnight-wraparound case.
+            Journey {
+                departure: 1430,
+                arrival: 10,
+                transport_mode: String::new(),
+                operating_period: 0,
+                line: String::new(),
+                controller: String::new(),
+            },
+        ];
+
+        let mut bytes = Vec::new();
+        write_packed_journeys(&mut bytes, &journeys);
+        let decoded = read_packed_journeys(&mut Cursor::new(&bytes)).unwrap();
+
+        let mut expected = journeys;
+        expected.sort_by_key(|journey| journey.arrival);
+        assert_eq!(decoded.len(), expected.len());
+        for (decoded, expected) in decoded.iter().zip(&expected) {
+            assert_eq!(decoded.arrival, expected.arrival);
+            assert_eq!(decoded.departure, expected.departure);
+            assert_eq!(decoded.operating_period, expected.operating_period);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Calendar arithmetic for the crate's `YYMMDD`-encoded dates (see
+//! `parser::NetexData::parse_date`), used to evaluate `OperatingPeriod.valid_day`
+//! bitsets and to prune periods by date range.
+
+/// Number of days from `from` to `to`, both `YYMMDD`-encoded (e.g. `220613` for
+/// 2022-06-13). Negative if `to` is earlier than `from`.
+pub fn days_between(from: u32, to: u32) -> i64 {
+    civil_day_number(to) - civil_day_number(from)
+}
+
+/// Day of week for a `YYMMDD`-encoded date: `0` is Monday, `6` is Sunday.
+pub fn weekday(yymmdd: u32) -> usize {
+    // 1970-01-01 (day number 0) was a Thursday, i.e. weekday index 3.
+    ((civil_day_number(yymmdd) + 3).rem_euclid(7)) as usize
+}
+
+fn civil_day_number(yymmdd: u32) -> i64 {
+    let year = 2000 + i64::from(yymmdd / 10000);
+    let month = i64::from((yymmdd / 100) % 100);
+    let day = i64::from(yymmdd % 100);
+    days_from_civil(year, month, day)
+}
+
+// Howard Hinnant's days-from-civil algorithm for the proleptic Gregorian calendar.
+// Returns the number of days since 1970-01-01.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn days_between_same_day() {
+        assert_eq!(super::days_between(220613, 220613), 0);
+    }
+
+    #[test]
+    fn days_between_across_month() {
+        assert_eq!(super::days_between(220630, 220701), 1);
+    }
+
+    #[test]
+    fn days_between_across_year() {
+        assert_eq!(super::days_between(221231, 230101), 1);
+    }
+
+    #[test]
+    fn weekday_known_date() {
+        // 2022-06-13 was a Monday.
+        assert_eq!(super::weekday(220613), 0);
+    }
+}
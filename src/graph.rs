@@ -4,6 +4,7 @@ use geo::{Centroid, HaversineDestination};
 use indicatif::ParallelProgressIterator;
 use rayon::iter::{IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator};
 
+use crate::date;
 use crate::parser::{
     Authority, DayTypeAssignment, Line, NetexData, ServiceJourney, UicOperatingPeriod,
 };
@@ -16,7 +17,7 @@ pub struct Node {
     pub lat: f32,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct Journey {
     #[serde(rename(serialize = "d"))]
     pub departure: u16,
@@ -59,10 +60,22 @@ pub struct Edge {
     pub walk_seconds: u16,
 }
 
-#[derive(Debug)]
+// Maps a node's centroid back to its index in `Graph.nodes`.
+type NodeTreeObj = rstar::primitives::GeomWithData<geo::Coord<f32>, usize>;
+
 pub struct Graph {
     pub nodes: Vec<Node>,
     pub edges: Vec<Edge>,
+    node_index: rstar::RTree<NodeTreeObj>,
+}
+
+impl std::fmt::Debug for Graph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Graph")
+            .field("nodes", &self.nodes)
+            .field("edges", &self.edges)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -361,10 +374,167 @@ impl Graph {
             edge.timetable.periods = local_ops;
         });
 
+        Self::new(nodes.vec, edges.into_values().collect())
+    }
+
+    /// Builds a `Graph` from already-deduplicated nodes and edges, indexing
+    /// the nodes for `nearest_node`/`nodes_within`. Used both by `from_data`
+    /// and by `from_binary`, which reconstructs the same two vectors from a
+    /// serialized container.
+    pub(crate) fn new(nodes: Vec<Node>, edges: Vec<Edge>) -> Graph {
+        let node_index = rstar::RTree::bulk_load(
+            nodes
+                .iter()
+                .enumerate()
+                .map(|(idx, node)| NodeTreeObj::new(geo::Coord::from([node.long, node.lat]), idx))
+                .collect(),
+        );
         Graph {
-            nodes: nodes.vec,
-            edges: edges.into_values().collect(),
+            nodes,
+            edges,
+            node_index,
+        }
+    }
+
+    /// Returns the node whose centroid is closest to `(lat, long)`.
+    pub fn nearest_node(&self, lat: f32, long: f32) -> Option<&Node> {
+        let point = geo::Coord::from([long, lat]);
+        self.node_index
+            .nearest_neighbor(&point)
+            .map(|entry| &self.nodes[entry.data])
+    }
+
+    /// Returns every node within `radius_m` meters of `(lat, long)`.
+    pub fn nodes_within(&self, lat: f32, long: f32, radius_m: f32) -> Vec<&Node> {
+        let center = geo::Point::from((long, lat));
+        // Corners on the diagonal must be radius * sqrt(2) away for the box
+        // to enclose the whole circle rather than be inscribed in it.
+        let corner_distance = radius_m * std::f32::consts::SQRT_2;
+        let corner1 = center.haversine_destination(45.0, corner_distance);
+        let corner2 = center.haversine_destination(225.0, corner_distance);
+        let aabb = rstar::AABB::<geo::Coord<f32>>::from_corners(corner1.into(), corner2.into());
+        self.node_index
+            .locate_in_envelope(&aabb)
+            .map(|entry| &self.nodes[entry.data])
+            .filter(|node| {
+                great_circle_distance((long, lat), (node.long, node.lat)) <= radius_m / 1000.0
+            })
+            .collect()
+    }
+
+    /// Prunes the graph to journeys whose `OperatingPeriod` overlaps
+    /// `[from, to]` (both `YYMMDD`-encoded, inclusive): periods entirely
+    /// outside the window are dropped, journeys referencing a dropped
+    /// period are dropped, edges left with no journeys and no walk
+    /// connection are dropped, and nodes left with no edges are dropped.
+    /// Returns a valid, possibly empty, graph rather than panicking when
+    /// the window matches nothing.
+    pub fn pruned_to_date_range(&self, from: u32, to: u32) -> Graph {
+        let mut edges: Vec<Edge> = self
+            .edges
+            .iter()
+            .filter_map(|edge| Self::prune_edge_to_date_range(edge, from, to))
+            .collect();
+
+        let mut keep = vec![false; self.nodes.len()];
+        for edge in &edges {
+            keep[edge.start_node] = true;
+            keep[edge.end_node] = true;
+        }
+        let mut old_to_new = vec![None; self.nodes.len()];
+        let mut nodes = Vec::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if keep[idx] {
+                old_to_new[idx] = Some(nodes.len());
+                nodes.push(node.clone());
+            }
+        }
+        for edge in &mut edges {
+            edge.start_node = old_to_new[edge.start_node].expect("edge kept a dropped node");
+            edge.end_node = old_to_new[edge.end_node].expect("edge kept a dropped node");
+        }
+
+        Self::new(nodes, edges)
+    }
+
+    // Trims `edge`'s periods to `[from, to]`, remaps surviving journeys onto
+    // the re-indexed periods, and drops journeys whose period was removed
+    // entirely. Returns `None` if nothing is left: no journeys and no walk
+    // connection to keep the edge alive for.
+    fn prune_edge_to_date_range(edge: &Edge, from: u32, to: u32) -> Option<Edge> {
+        let mut old_to_new = vec![None; edge.timetable.periods.len()];
+        let mut periods = Vec::new();
+        for (idx, period) in edge.timetable.periods.iter().enumerate() {
+            if let Some(trimmed) = Self::trim_operating_period(period, from, to) {
+                old_to_new[idx] = Some(periods.len());
+                periods.push(trimmed);
+            }
+        }
+
+        let journeys: Vec<Journey> = edge
+            .timetable
+            .journeys
+            .iter()
+            .filter_map(|journey| {
+                let new_period = old_to_new.get(journey.operating_period).copied().flatten()?;
+                Some(Journey {
+                    operating_period: new_period,
+                    ..journey.clone()
+                })
+            })
+            .collect();
+
+        if journeys.is_empty() && edge.walk_seconds == u16::MAX {
+            return None;
         }
+
+        Some(Edge {
+            start_node: edge.start_node,
+            end_node: edge.end_node,
+            walk_seconds: edge.walk_seconds,
+            timetable: Timetable { journeys, periods },
+        })
+    }
+
+    // Clips `period` to its overlap with `[from, to]`, slicing `valid_day`
+    // consistently so its day-of-operation bits still line up with the
+    // trimmed `[from, to]`. Returns `None` if the period does not overlap
+    // the window at all.
+    fn trim_operating_period(period: &OperatingPeriod, from: u32, to: u32) -> Option<OperatingPeriod> {
+        if period.to < from || period.from > to {
+            return None;
+        }
+        let new_from = period.from.max(from);
+        let new_to = period.to.min(to);
+        // An inverted `[from, to]` window (e.g. `--from` after `--to`) can
+        // still pass the overlap check above if `period` spans the gap
+        // between them; treat it the same as "no overlap" instead of
+        // underflowing `days_between` below.
+        if new_from > new_to {
+            return None;
+        }
+        let start_offset = usize::try_from(date::days_between(period.from, new_from))
+            .expect("trimmed start precedes period start");
+        let day_count = usize::try_from(date::days_between(new_from, new_to) + 1)
+            .expect("trimmed end precedes trimmed start");
+
+        let mut valid_day = vec![0_u8; day_count.div_ceil(8)];
+        for day in 0..day_count {
+            let old_bit = start_offset + day;
+            let Some(byte) = period.valid_day.get(old_bit / 8) else {
+                break;
+            };
+            if (byte >> (old_bit % 8)) & 1 == 1 {
+                valid_day[day / 8] |= 1 << (day % 8);
+            }
+        }
+
+        Some(OperatingPeriod {
+            from: new_from,
+            to: new_to,
+            valid_day_bits: base64::encode(&valid_day),
+            valid_day,
+        })
     }
 
     fn update_walk(walk_edge: &WalkEdge, nodes: &Nodes, edges: &mut HashMap<(usize, usize), Edge>) {
@@ -434,7 +604,7 @@ impl Graph {
     }
 }
 
-fn great_circle_distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+pub(crate) fn great_circle_distance(a: (f32, f32), b: (f32, f32)) -> f32 {
     use std::f32::consts;
     let a_lon = a.0 * consts::PI / 180.0;
     let a_lat = a.1 * consts::PI / 180.0;
@@ -446,3 +616,117 @@ fn great_circle_distance(a: (f32, f32), b: (f32, f32)) -> f32 {
     let angle = intermediate.acos();
     6371.009 * angle
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: u64) -> Node {
+        Node {
+            id,
+            short_name: id.to_string(),
+            long: 0.0,
+            lat: 0.0,
+        }
+    }
+
+    #[test]
+    fn trim_operating_period_reslices_valid_day_at_the_right_offsets() {
+        // A 5-day period (220101..=220105) valid on days 0, 2 and 4
+        // (0b0001_0101), trimmed to the middle 3 days (220102..=220104).
+        // Day 0 of the trimmed period is day 1 of the original (not valid),
+        // day 1 is day 2 of the original (valid), day 2 is day 3 (not
+        // valid) — so only bit 1 should end up set.
+        let period = OperatingPeriod {
+            from: 220_101,
+            to: 220_105,
+            valid_day_bits: String::new(),
+            valid_day: vec![0b0001_0101],
+        };
+
+        let trimmed = Graph::trim_operating_period(&period, 220_102, 220_104).unwrap();
+
+        assert_eq!(trimmed.from, 220_102);
+        assert_eq!(trimmed.to, 220_104);
+        assert_eq!(trimmed.valid_day, vec![0b0000_0010]);
+    }
+
+    #[test]
+    fn trim_operating_period_returns_none_when_windows_dont_overlap() {
+        let period = OperatingPeriod {
+            from: 220_101,
+            to: 220_105,
+            valid_day_bits: String::new(),
+            valid_day: vec![0b0001_1111],
+        };
+
+        assert!(Graph::trim_operating_period(&period, 220_110, 220_115).is_none());
+    }
+
+    #[test]
+    fn pruned_to_date_range_with_no_overlap_yields_an_empty_but_valid_graph() {
+        let nodes = vec![node(1), node(2)];
+        let edges = vec![Edge {
+            start_node: 0,
+            end_node: 1,
+            walk_seconds: u16::MAX,
+            timetable: Timetable {
+                journeys: vec![Journey {
+                    departure: 480,
+                    arrival: 490,
+                    transport_mode: "bus".to_owned(),
+                    operating_period: 0,
+                    line: "1".to_owned(),
+                    controller: "Acme".to_owned(),
+                }],
+                periods: vec![OperatingPeriod {
+                    from: 220_101,
+                    to: 220_105,
+                    valid_day_bits: String::new(),
+                    valid_day: vec![0b0001_1111],
+                }],
+            },
+        }];
+        let graph = Graph::new(nodes, edges);
+
+        let pruned = graph.pruned_to_date_range(220_110, 220_115);
+
+        assert!(pruned.nodes.is_empty());
+        assert!(pruned.edges.is_empty());
+    }
+
+    #[test]
+    fn nearest_node_and_nodes_within_agree_on_distance() {
+        // Three nodes roughly 0, 111km and 222km east of the origin (1
+        // degree of longitude at the equator is ~111km).
+        let nodes = vec![
+            Node {
+                id: 1,
+                short_name: "origin".to_owned(),
+                long: 0.0,
+                lat: 0.0,
+            },
+            Node {
+                id: 2,
+                short_name: "near".to_owned(),
+                long: 1.0,
+                lat: 0.0,
+            },
+            Node {
+                id: 3,
+                short_name: "far".to_owned(),
+                long: 2.0,
+                lat: 0.0,
+            },
+        ];
+        let graph = Graph::new(nodes, vec![]);
+
+        let nearest = graph.nearest_node(0.001, 0.001).unwrap();
+        assert_eq!(nearest.short_name, "origin");
+
+        let within = graph.nodes_within(0.0, 0.0, 150_000.0);
+        let mut names: Vec<&str> = within.iter().map(|node| node.short_name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["near", "origin"]);
+    }
+}
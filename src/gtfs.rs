@@ -0,0 +1,464 @@
+//! GTFS feed loader. Maps a GTFS feed's `stops.txt`, `stop_times.txt`,
+//! `trips.txt`, `routes.txt`, `agency.txt` and `calendar.txt`/
+//! `calendar_dates.txt` into the same `NetexData` structures the NeTEx loader
+//! in `parser` produces, so `Graph::from_data` and everything downstream of it
+//! (dedup, journey filtering, serialization) runs unchanged regardless of
+//! source format.
+
+use std::collections::HashMap;
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::date;
+use crate::parser::{
+    Authority, DayTypeAssignment, Line, NetexData, ScheduledStopPoint, ServiceJourney,
+    ServiceJourneyPattern, StopPointInJourneyPattern, TimetabledPassingTime, UicOperatingPeriod,
+};
+
+/// The raw contents of the GTFS text files that make up a feed. `calendar`
+/// and `calendar_dates` are optional, mirroring the GTFS spec (a feed may
+/// define service exclusively through one or the other).
+pub struct GtfsFeed<'a> {
+    pub agency: &'a [u8],
+    pub stops: &'a [u8],
+    pub routes: &'a [u8],
+    pub trips: &'a [u8],
+    pub stop_times: &'a [u8],
+    pub calendar: Option<&'a [u8]>,
+    pub calendar_dates: Option<&'a [u8]>,
+}
+
+#[derive(serde::Deserialize)]
+struct GtfsAgency {
+    agency_id: Option<String>,
+    agency_name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct GtfsStop {
+    stop_id: String,
+    stop_name: String,
+    stop_lat: f32,
+    stop_lon: f32,
+}
+
+#[derive(serde::Deserialize)]
+struct GtfsRoute {
+    route_id: String,
+    agency_id: Option<String>,
+    route_short_name: String,
+    route_type: u16,
+}
+
+#[derive(serde::Deserialize)]
+struct GtfsTrip {
+    route_id: String,
+    service_id: String,
+    trip_id: String,
+}
+
+#[derive(serde::Deserialize)]
+struct GtfsStopTime {
+    trip_id: String,
+    arrival_time: String,
+    departure_time: String,
+    stop_id: String,
+    stop_sequence: u32,
+}
+
+#[derive(serde::Deserialize)]
+struct GtfsCalendar {
+    service_id: String,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    start_date: u32,
+    end_date: u32,
+}
+
+#[derive(serde::Deserialize)]
+struct GtfsCalendarDate {
+    service_id: String,
+    date: u32,
+    exception_type: u8,
+}
+
+// Weekly service pattern plus single-date overrides for one GTFS service_id,
+// collected from calendar.txt/calendar_dates.txt before being flattened into
+// the crate's `UicOperatingPeriod` day-bitset representation.
+#[derive(Default)]
+struct ServiceCalendar {
+    weekday: [bool; 7],
+    start_date: Option<u32>,
+    end_date: Option<u32>,
+    exceptions: Vec<(u32, bool)>, // (gtfs YYYYMMDD date, added)
+}
+
+fn read_csv<T: serde::de::DeserializeOwned>(
+    bytes: &[u8],
+) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+    let mut reader = csv::ReaderBuilder::new().from_reader(bytes);
+    reader
+        .deserialize()
+        .collect::<Result<Vec<T>, csv::Error>>()
+        .map_err(Into::into)
+}
+
+// Converts a GTFS calendar.txt/calendar_dates.txt date (YYYYMMDD) into the
+// crate's YYMMDD-encoded date (see parser::NetexData::parse_date).
+fn to_crate_date(gtfs_date: u32) -> u32 {
+    (gtfs_date / 10000 % 100) * 10000 + (gtfs_date / 100 % 100) * 100 + gtfs_date % 100
+}
+
+// GTFS allows `arrival_time`/`departure_time` to be blank for
+// non-timepoint stops, with the expectation that consumers interpolate
+// them. Returns None for a blank field instead of handing an empty
+// string to `NetexData::parse_minutes`, which assumes a populated
+// "HH:MM:SS" value.
+fn parse_minutes_checked(value: &str) -> Option<u16> {
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(NetexData::parse_minutes(value))
+    }
+}
+
+// Fills blank (None) entries by linearly interpolating between the
+// nearest known times on either side, by stop position (GTFS doesn't
+// give us distances, so we assume even spacing). A run of blanks at the
+// very start or end of a trip is clamped to the nearest known time.
+fn interpolate_times(times: &[Option<u16>]) -> Vec<u16> {
+    let mut result = vec![0_u16; times.len()];
+    let mut i = 0;
+    while i < times.len() {
+        let Some(known) = times[i] else {
+            i += 1;
+            continue;
+        };
+        result[i] = known;
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < times.len() {
+        if times[i].is_some() {
+            i += 1;
+            continue;
+        }
+        let gap_start = i;
+        let mut gap_end = i;
+        while gap_end < times.len() && times[gap_end].is_none() {
+            gap_end += 1;
+        }
+        let prev = gap_start.checked_sub(1).map(|idx| result[idx]);
+        let next = times.get(gap_end).copied().flatten();
+        match (prev, next) {
+            (Some(prev), Some(next)) => {
+                let span = (gap_end - gap_start + 1) as f32;
+                for (offset, slot) in (gap_start..gap_end).enumerate() {
+                    let frac = (offset + 1) as f32 / span;
+                    result[slot] = (prev as f32 + (next as f32 - prev as f32) * frac).round() as u16;
+                }
+            }
+            (Some(prev), None) => result[gap_start..gap_end].fill(prev),
+            (None, Some(next)) => result[gap_start..gap_end].fill(next),
+            (None, None) => {}
+        }
+        i = gap_end;
+    }
+    result
+}
+
+fn transport_mode_name(route_type: u16) -> String {
+    match route_type {
+        0 => "tram",
+        1 => "metro",
+        2 => "rail",
+        3 => "bus",
+        4 => "water",
+        6 => "cableway",
+        7 => "funicular",
+        _ => "unknown",
+    }
+    .to_owned()
+}
+
+impl NetexData {
+    /// Parses a GTFS feed into the same representation `from_xml` produces
+    /// for NeTEx documents.
+    pub fn from_gtfs(feed: &GtfsFeed) -> Result<NetexData, Box<dyn std::error::Error>> {
+        let mut data = NetexData::default();
+
+        let agencies: Vec<GtfsAgency> = read_csv(feed.agency)?;
+        data.authorities = agencies
+            .iter()
+            .map(|agency| Authority {
+                id: xxh3_64(agency.agency_id.as_deref().unwrap_or_default().as_bytes()),
+                short_name: agency.agency_name.clone(),
+            })
+            .collect();
+        // GTFS lets a route omit agency_id when the feed has a single agency,
+        // whether or not that agency itself specified one — resolve such
+        // routes to the one agency's actual id rather than `xxh3_64(b"")`.
+        let default_agency_id: Option<&str> = match agencies.as_slice() {
+            [only] => only.agency_id.as_deref(),
+            _ => None,
+        };
+
+        let stops: Vec<GtfsStop> = read_csv(feed.stops)?;
+        data.scheduled_stop_points = stops
+            .iter()
+            .map(|stop| ScheduledStopPoint {
+                id: xxh3_64(stop.stop_id.as_bytes()),
+                short_name: stop.stop_name.replace('"', ""),
+                long: stop.stop_lon.clamp(-180.0, 180.0),
+                lat: stop.stop_lat.clamp(-90.0, 90.0),
+            })
+            .collect();
+
+        let routes: Vec<GtfsRoute> = read_csv(feed.routes)?;
+        let route_types: HashMap<&str, u16> = routes
+            .iter()
+            .map(|route| (route.route_id.as_str(), route.route_type))
+            .collect();
+        data.lines = routes
+            .iter()
+            .map(|route| Line {
+                id: xxh3_64(route.route_id.as_bytes()),
+                short_name: route.route_short_name.clone(),
+                authority: xxh3_64(
+                    route
+                        .agency_id
+                        .as_deref()
+                        .or(default_agency_id)
+                        .unwrap_or_default()
+                        .as_bytes(),
+                ),
+            })
+            .collect();
+
+        let trips: Vec<GtfsTrip> = read_csv(feed.trips)?;
+        let trip_route: HashMap<&str, &str> = trips
+            .iter()
+            .map(|trip| (trip.trip_id.as_str(), trip.route_id.as_str()))
+            .collect();
+        let trip_service: HashMap<&str, &str> = trips
+            .iter()
+            .map(|trip| (trip.trip_id.as_str(), trip.service_id.as_str()))
+            .collect();
+
+        let mut stop_times: Vec<GtfsStopTime> = read_csv(feed.stop_times)?;
+        stop_times.sort_by(|a, b| {
+            a.trip_id
+                .cmp(&b.trip_id)
+                .then(a.stop_sequence.cmp(&b.stop_sequence))
+        });
+
+        let mut stop_times_by_trip = Vec::<&[GtfsStopTime]>::new();
+        let mut start = 0;
+        for end in 1..=stop_times.len() {
+            if end == stop_times.len() || stop_times[end].trip_id != stop_times[start].trip_id {
+                stop_times_by_trip.push(&stop_times[start..end]);
+                start = end;
+            }
+        }
+
+        let mut patterns = Vec::<ServiceJourneyPattern>::new();
+        let mut journeys = Vec::<ServiceJourney>::new();
+        for trip_stop_times in stop_times_by_trip {
+            let trip_id = &trip_stop_times[0].trip_id;
+            let Some(&route_id) = trip_route.get(trip_id.as_str()) else {
+                continue;
+            };
+            let Some(&service_id) = trip_service.get(trip_id.as_str()) else {
+                continue;
+            };
+            let pattern_id = xxh3_64(trip_id.as_bytes());
+            // Blank arrival/departure times (allowed by GTFS for
+            // non-timepoint stops) are filled in by interpolating between
+            // the nearest stops with an explicit time.
+            let interpolated = interpolate_times(
+                &trip_stop_times
+                    .iter()
+                    .map(|stop_time| {
+                        parse_minutes_checked(&stop_time.arrival_time)
+                            .or_else(|| parse_minutes_checked(&stop_time.departure_time))
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            let mut stops = Vec::with_capacity(trip_stop_times.len());
+            let mut passing_times = Vec::with_capacity(trip_stop_times.len());
+            for (stop_time, &filled) in trip_stop_times.iter().zip(&interpolated) {
+                let point_ref =
+                    xxh3_64(format!("{}:{}", stop_time.trip_id, stop_time.stop_sequence).as_bytes());
+                stops.push(StopPointInJourneyPattern {
+                    id: point_ref,
+                    scheduled_stop_point: xxh3_64(stop_time.stop_id.as_bytes()),
+                });
+                passing_times.push(TimetabledPassingTime {
+                    stop_point_in_journey_pattern: point_ref,
+                    arrival: parse_minutes_checked(&stop_time.arrival_time).unwrap_or(filled),
+                    departure: parse_minutes_checked(&stop_time.departure_time).unwrap_or(filled),
+                });
+            }
+            patterns.push(ServiceJourneyPattern {
+                id: pattern_id,
+                line: xxh3_64(route_id.as_bytes()),
+                stops,
+            });
+            journeys.push(ServiceJourney {
+                passing_times,
+                day_type: xxh3_64(service_id.as_bytes()),
+                transport_mode: transport_mode_name(
+                    route_types.get(route_id).copied().unwrap_or_default(),
+                ),
+                pattern_ref: pattern_id,
+            });
+        }
+        data.service_journey_patterns = patterns;
+        data.service_journeys = journeys;
+
+        let (operating_periods, day_type_assignments) = Self::build_calendars(feed)?;
+        data.operating_periods = operating_periods;
+        data.day_type_assignments = day_type_assignments;
+
+        Ok(data)
+    }
+
+    fn build_calendars(
+        feed: &GtfsFeed,
+    ) -> Result<(Vec<UicOperatingPeriod>, Vec<DayTypeAssignment>), Box<dyn std::error::Error>>
+    {
+        let mut calendars = HashMap::<String, ServiceCalendar>::new();
+        if let Some(calendar) = feed.calendar {
+            for row in read_csv::<GtfsCalendar>(calendar)? {
+                let entry = calendars.entry(row.service_id).or_default();
+                entry.start_date = Some(row.start_date);
+                entry.end_date = Some(row.end_date);
+                entry.weekday = [
+                    row.monday != 0,
+                    row.tuesday != 0,
+                    row.wednesday != 0,
+                    row.thursday != 0,
+                    row.friday != 0,
+                    row.saturday != 0,
+                    row.sunday != 0,
+                ];
+            }
+        }
+        if let Some(calendar_dates) = feed.calendar_dates {
+            for row in read_csv::<GtfsCalendarDate>(calendar_dates)? {
+                calendars
+                    .entry(row.service_id)
+                    .or_default()
+                    .exceptions
+                    .push((row.date, row.exception_type == 1));
+            }
+        }
+
+        let mut operating_periods = Vec::new();
+        let mut day_type_assignments = Vec::new();
+        for (service_id, calendar) in &calendars {
+            let period_id = xxh3_64(service_id.as_bytes());
+            let from = calendar
+                .start_date
+                .into_iter()
+                .chain(calendar.exceptions.iter().map(|(date, _)| *date))
+                .min();
+            let to = calendar
+                .end_date
+                .into_iter()
+                .chain(calendar.exceptions.iter().map(|(date, _)| *date))
+                .max();
+            let (Some(from), Some(to)) = (from, to) else {
+                continue;
+            };
+            let from = to_crate_date(from);
+            let to = to_crate_date(to);
+            let span = usize::try_from(date::days_between(from, to).max(0) + 1).unwrap_or(0);
+            let mut valid_day = vec![0_u8; span.div_ceil(8)];
+            for offset in 0..span {
+                let day = offset as u32;
+                let weekday = weekday_of(from, day);
+                if calendar.weekday[weekday] {
+                    valid_day[offset / 8] |= 1 << (offset % 8);
+                }
+            }
+            for (date, added) in &calendar.exceptions {
+                let crate_date = to_crate_date(*date);
+                let Ok(offset) = usize::try_from(date::days_between(from, crate_date)) else {
+                    continue;
+                };
+                if offset / 8 >= valid_day.len() {
+                    continue;
+                }
+                if *added {
+                    valid_day[offset / 8] |= 1 << (offset % 8);
+                } else {
+                    valid_day[offset / 8] &= !(1 << (offset % 8));
+                }
+            }
+            operating_periods.push(UicOperatingPeriod {
+                id: period_id,
+                from,
+                to,
+                valid_day_bits: valid_day,
+            });
+            day_type_assignments.push(DayTypeAssignment {
+                operating_period: period_id,
+                day_type: period_id,
+                is_available: true,
+            });
+        }
+        Ok((operating_periods, day_type_assignments))
+    }
+}
+
+fn weekday_of(from: u32, offset: u32) -> usize {
+    (date::weekday(from) + offset as usize) % 7
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_gtfs_interpolates_blank_times_at_a_non_timepoint_stop() {
+        let agency = b"agency_id,agency_name\nA,Acme Transit\n";
+        let stops = b"stop_id,stop_name,stop_lat,stop_lon\n\
+            S1,First,50.0,10.0\n\
+            S2,Mid,50.1,10.1\n\
+            S3,Last,50.2,10.2\n";
+        let routes = b"route_id,agency_id,route_short_name,route_type\nR1,A,1,3\n";
+        let trips = b"route_id,service_id,trip_id\nR1,WD,T1\n";
+        // The middle stop is a non-timepoint: both times are blank, as
+        // GTFS explicitly permits.
+        let stop_times = b"trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+            T1,10:00:00,10:00:00,S1,0\n\
+            T1,,,S2,1\n\
+            T1,10:20:00,10:20:00,S3,2\n";
+        let feed = GtfsFeed {
+            agency,
+            stops,
+            routes,
+            trips,
+            stop_times,
+            calendar: None,
+            calendar_dates: None,
+        };
+
+        let data = NetexData::from_gtfs(&feed).unwrap();
+
+        assert_eq!(data.service_journeys.len(), 1);
+        let passing_times = &data.service_journeys[0].passing_times;
+        assert_eq!(passing_times.len(), 3);
+        assert_eq!(passing_times[0].arrival, 600);
+        assert_eq!(passing_times[1].arrival, 610);
+        assert_eq!(passing_times[1].departure, 610);
+        assert_eq!(passing_times[2].arrival, 620);
+    }
+}
@@ -1,19 +1,34 @@
 use std::{io::Write, path::PathBuf};
 
 use clap::{Parser, ValueEnum};
-use indicatif::ParallelProgressIterator;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use zip::ZipArchive;
 
-use crate::{graph::WalkEdge, parser::NetexData};
+use crate::{graph::WalkEdge, gtfs::GtfsFeed, parser::NetexData};
 
+mod binary;
+mod date;
 mod graph;
+mod gtfs;
 mod parser;
+mod petgraph_view;
+mod region;
+mod routing;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum OutputFormat {
     Csv,
     Binary,
+    /// Binary with per-edge journeys delta+varint encoded instead of fixed
+    /// 6-byte records.
+    BinaryPacked,
+}
+
+/// Format of the documents inside the input zip.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum InputFormat {
+    Netex,
+    Gtfs,
 }
 
 /// Multi-threaded parser for public transport information in the netex format
@@ -21,10 +36,29 @@ pub enum OutputFormat {
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 pub struct Args {
-    /// Path to a zip file containing netex documents.
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Parse a netex or GTFS feed into a graph.
+    Parse(ParseArgs),
+    /// Prune an existing graph.bin to journeys overlapping a date range.
+    Range(RangeArgs),
+}
+
+#[derive(Parser, Debug)]
+struct ParseArgs {
+    /// Path to a zip file containing netex documents, or a GTFS feed zip when
+    /// `--input-format gtfs` is used.
     #[clap()]
     netex_file: PathBuf,
 
+    /// Format of the documents inside the input zip.
+    #[clap(short, long, value_parser, value_enum, default_value = "netex")]
+    input_format: InputFormat,
+
     /// Output format. CSV creates a nodes.csv and edges.csv file.
     #[clap(short, long, value_parser, value_enum)]
     output_format: OutputFormat,
@@ -36,10 +70,71 @@ pub struct Args {
     /// Substring the netex documents file names must included.
     #[clap(short, long, default_value = "")]
     filter: String,
+
+    /// Path to a TOML config file describing the regions stops must fall
+    /// inside to be kept. When omitted, no region filtering is applied.
+    #[clap(short, long)]
+    config: Option<PathBuf>,
+
+    /// Number of parsed documents allowed to sit in the channel between the
+    /// parser threads and the consumer before a parser thread blocks. This
+    /// is a backpressure knob, not a memory limit: lowering it keeps parser
+    /// threads from racing ahead of a slow consumer, but every document
+    /// still ends up held in memory together once parsing finishes, since
+    /// graph building needs the whole feed at once to dedupe stops and
+    /// resolve lines/authorities/operating periods across it.
+    #[clap(long, default_value = "64")]
+    batch_size: usize,
+
+    /// Compress binary output (graph.bin's sections with deflate, nodes.json
+    /// with gzip). Ignored for `--output-format csv`.
+    #[clap(long)]
+    compress: bool,
+
+    /// Omit the per-section checksums from graph.bin for the smallest
+    /// possible file. Ignored for `--output-format csv`.
+    #[clap(long)]
+    skip_checksums: bool,
+}
+
+#[derive(Parser, Debug)]
+struct RangeArgs {
+    /// Path to a `graph.bin` written by `parse --output-format binary`.
+    #[clap()]
+    graph_file: PathBuf,
+
+    /// Start of the date window, `YYMMDD` encoded (e.g. `220613` for
+    /// 2022-06-13), inclusive.
+    #[clap(long)]
+    from: u32,
+
+    /// End of the date window, `YYMMDD` encoded, inclusive.
+    #[clap(long)]
+    to: u32,
+
+    /// Output format for the pruned graph.
+    #[clap(short, long, value_parser, value_enum, default_value = "binary")]
+    output_format: OutputFormat,
+
+    /// Compress binary output (graph.bin's sections with deflate, nodes.json
+    /// with gzip). Ignored for `--output-format csv`.
+    #[clap(long)]
+    compress: bool,
+
+    /// Omit the per-section checksums from graph.bin for the smallest
+    /// possible file. Ignored for `--output-format csv`.
+    #[clap(long)]
+    skip_checksums: bool,
 }
 
 fn main() {
-    let args = Args::parse();
+    match Args::parse().command {
+        Command::Parse(args) => run_parse(args),
+        Command::Range(args) => run_range(args),
+    }
+}
+
+fn run_parse(args: ParseArgs) {
     let zip_stream = std::fs::File::open(args.netex_file).expect("failed to open data");
     let zip_memmap = unsafe { memmap::Mmap::map(&zip_stream).expect("failed mmap") };
     let zip_cursor = std::io::Cursor::new(&zip_memmap);
@@ -57,46 +152,157 @@ fn main() {
             serde_json::from_slice(&walk_bytes).expect("failed to deserialize json")
         }
     };
-    let graph = parse(&zip_memmap, &documents, &walkways);
+    let region_filter = args.config.map(|path| {
+        let contents = std::fs::read_to_string(path).expect("failed to read region config");
+        region::RegionFilter::from_toml(&contents).expect("failed to parse region config")
+    });
+    let graph = match args.input_format {
+        InputFormat::Netex => parse(
+            &zip_memmap,
+            &documents,
+            &walkways,
+            region_filter.as_ref(),
+            args.batch_size,
+        ),
+        InputFormat::Gtfs => parse_gtfs(&zip_memmap, &walkways, region_filter.as_ref())
+            .expect("failed to read gtfs feed"),
+    };
     println!(
         "{} has {} deduped nodes and {} deduped edges.",
         args.filter,
         graph.nodes.len(),
         graph.edges.len(),
     );
-    match args.output_format {
-        OutputFormat::Csv => dump_csv(&graph).expect("failed to dump csv"),
-        OutputFormat::Binary => dump_binary(&graph).expect("failed to dump binary"),
+    dump(
+        &graph,
+        args.output_format,
+        args.compress,
+        args.skip_checksums,
+    );
+}
+
+fn run_range(args: RangeArgs) {
+    let bytes = std::fs::read(args.graph_file).expect("failed to read graph.bin");
+    let graph = graph::Graph::from_binary(&bytes).expect("failed to decode graph.bin");
+    let pruned = graph.pruned_to_date_range(args.from, args.to);
+    println!(
+        "pruned to {} nodes and {} edges in [{}, {}].",
+        pruned.nodes.len(),
+        pruned.edges.len(),
+        args.from,
+        args.to,
+    );
+    dump(
+        &pruned,
+        args.output_format,
+        args.compress,
+        args.skip_checksums,
+    );
+}
+
+fn dump(graph: &graph::Graph, output_format: OutputFormat, compress: bool, skip_checksums: bool) {
+    match output_format {
+        OutputFormat::Csv => dump_csv(graph).expect("failed to dump csv"),
+        OutputFormat::Binary => {
+            dump_binary(graph, false, compress, skip_checksums).expect("failed to dump binary");
+        }
+        OutputFormat::BinaryPacked => {
+            dump_binary(graph, true, compress, skip_checksums).expect("failed to dump binary");
+        }
     }
 }
 
-fn parse(archive: &memmap::Mmap, documents: &[String], walkways: &[WalkEdge]) -> graph::Graph {
-    let mut data = documents
-        .par_iter()
-        .progress_count(documents.len() as u64)
-        .map(|doc| {
-            let zip_cursor = std::io::Cursor::new(archive);
-            let mut archive = ZipArchive::new(zip_cursor).expect("failed to read zip");
-            let file = archive.by_name(doc).expect("failed to find document");
-            if file.is_dir() {
-                return Vec::new();
-            }
-            let size = file.size().try_into().expect("u64 does not fit usize");
-            vec![parser::NetexData::from_xml(file, size).unwrap_or_default()]
-        })
-        .reduce(Vec::<NetexData>::new, |mut accum, item| {
-            accum.extend(item);
-            accum
+// Parses `documents` on a rayon thread pool and streams each result over a
+// channel bounded to `batch_size` in-flight documents, so a slow consumer
+// backpressures the producers instead of letting parsed-but-unconsumed
+// documents pile up ahead of it. The consumer thread drains arrivals into
+// `data`, advancing the progress bar as it goes.
+//
+// This is a throughput/backpressure knob, not a peak-memory fix: `data`
+// collects every parsed document before `graph::Graph::from_data` runs,
+// since deduping stops and resolving lines/authorities/operating periods
+// needs the whole feed at once regardless of how it arrived.
+fn parse(
+    archive: &memmap::Mmap,
+    documents: &[String],
+    walkways: &[WalkEdge],
+    region_filter: Option<&region::RegionFilter>,
+    batch_size: usize,
+) -> graph::Graph {
+    let bar = indicatif::ProgressBar::new(documents.len() as u64);
+    let data = std::thread::scope(|scope| {
+        let (tx, rx) = std::sync::mpsc::sync_channel::<NetexData>(batch_size);
+        scope.spawn(move || {
+            documents.par_iter().for_each(|doc| {
+                let zip_cursor = std::io::Cursor::new(archive);
+                let mut archive = ZipArchive::new(zip_cursor).expect("failed to read zip");
+                let file = archive.by_name(doc).expect("failed to find document");
+                if file.is_dir() {
+                    return;
+                }
+                let size = file.size().try_into().expect("u64 does not fit usize");
+                let mut parsed = parser::NetexData::from_xml(file, size).unwrap_or_default();
+                if let Some(region_filter) = region_filter {
+                    parsed
+                        .scheduled_stop_points
+                        .retain(|stop| region_filter.contains(stop.long, stop.lat));
+                }
+                tx.send(parsed).expect("consumer hung up");
+            });
         });
+
+        let mut data = Vec::with_capacity(documents.len());
+        for parsed in rx {
+            bar.inc(1);
+            data.push(parsed);
+        }
+        data
+    });
+    bar.finish();
+
     println!("deduping...");
-    for d in &mut data {
-        d.scheduled_stop_points.retain(|stop| {
-            stop.long > 5.5 && stop.long < 15.5 && stop.lat > 47.0 && stop.lat < 55.5
-        });
-    }
     graph::Graph::from_data(&data, walkways)
 }
 
+fn parse_gtfs(
+    archive: &memmap::Mmap,
+    walkways: &[WalkEdge],
+    region_filter: Option<&region::RegionFilter>,
+) -> Result<graph::Graph, Box<dyn std::error::Error>> {
+    let zip_cursor = std::io::Cursor::new(archive);
+    let mut archive = ZipArchive::new(zip_cursor)?;
+    let mut read_file = |name: &str| -> std::io::Result<Vec<u8>> {
+        let mut file = archive.by_name(name)?;
+        let mut buf = Vec::with_capacity(file.size().try_into().unwrap_or(0));
+        std::io::Read::read_to_end(&mut file, &mut buf)?;
+        Ok(buf)
+    };
+    let agency = read_file("agency.txt")?;
+    let stops = read_file("stops.txt")?;
+    let routes = read_file("routes.txt")?;
+    let trips = read_file("trips.txt")?;
+    let stop_times = read_file("stop_times.txt")?;
+    let calendar = read_file("calendar.txt").ok();
+    let calendar_dates = read_file("calendar_dates.txt").ok();
+    let feed = GtfsFeed {
+        agency: &agency,
+        stops: &stops,
+        routes: &routes,
+        trips: &trips,
+        stop_times: &stop_times,
+        calendar: calendar.as_deref(),
+        calendar_dates: calendar_dates.as_deref(),
+    };
+    println!("parsing gtfs feed...");
+    let mut data = [parser::NetexData::from_gtfs(&feed)?];
+    if let Some(region_filter) = region_filter {
+        data[0]
+            .scheduled_stop_points
+            .retain(|stop| region_filter.contains(stop.long, stop.lat));
+    }
+    Ok(graph::Graph::from_data(&data, walkways))
+}
+
 fn dump_csv(graph: &graph::Graph) -> Result<(), Box<dyn std::error::Error>> {
     let mut opts = std::fs::OpenOptions::new();
     opts.write(true).create(true).truncate(true);
@@ -143,77 +349,19 @@ struct MetaNode {
     coords: [f32; 2],
 }
 
+// Writes graph.bin and nodes.json. When `compress` is set, graph.bin's
+// sections are deflated (see `binary::encode`) and nodes.json is gzipped to
+// `nodes.json.gz`, since it's read directly by a JS web consumer (see
+// `MetaNode`) that can transparently gunzip it.
 #[allow(clippy::cast_possible_truncation)]
-fn dump_binary(graph: &graph::Graph) -> Result<(), Box<dyn std::error::Error>> {
-    fn node_as_bytes(node: &graph::Node) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        // id is implicit
-        let mut data = Vec::<u8>::new();
-        let mut writer = std::io::Cursor::new(&mut data);
-        writer.write_all(&node.id.to_le_bytes())?;
-        writer.write_all(&node.lat.to_le_bytes())?;
-        writer.write_all(&node.long.to_le_bytes())?;
-        let name_bytes = node.short_name.as_bytes();
-        writer.write_all(&(name_bytes.len() as u32).to_le_bytes())?;
-        writer.write_all(name_bytes)?;
-        Ok(data)
-    }
-
-    fn period_as_bytes(
-        period: &graph::OperatingPeriod,
-    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        let mut data = Vec::<u8>::new();
-        let mut writer = std::io::Cursor::new(&mut data);
-        writer.write_all(&period.from.to_le_bytes())?;
-        writer.write_all(&period.to.to_le_bytes())?;
-        writer.write_all(&(period.valid_day.len() as u32).to_le_bytes())?;
-        writer.write_all(&period.valid_day)?;
-        Ok(data)
-    }
-
-    fn edge_as_bytes(edge: &graph::Edge) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        let mut data = Vec::<u8>::new();
-        let mut writer = std::io::Cursor::new(&mut data);
-        writer.write_all(&(edge.start_node as u32).to_le_bytes())?;
-        writer.write_all(&(edge.end_node as u32).to_le_bytes())?;
-        writer.write_all(&edge.walk_seconds.to_le_bytes())?;
-        let journeys = &edge.timetable.journeys;
-        // arrival, departure, operating period -> 3x u16
-        writer.write_all(&((journeys.len() * 6) as u32).to_le_bytes())?;
-        for journey in journeys {
-            writer.write_all(&journey.arrival.to_le_bytes())?;
-            writer.write_all(&journey.departure.to_le_bytes())?;
-            writer.write_all(&(journey.operating_period as u16).to_le_bytes())?;
-        }
-        let mut periods = Vec::<u8>::new();
-        for period in &edge.timetable.periods {
-            periods.extend(period_as_bytes(period)?);
-        }
-        writer.write_all(&(periods.len() as u32).to_le_bytes())?;
-        writer.write_all(&periods)?;
-        Ok(data)
-    }
-
-    let mut opts = std::fs::OpenOptions::new();
-    opts.write(true).create(true).truncate(true);
-    let mut writer = std::io::BufWriter::new(opts.open("./graph.bin")?);
-    // TODO: magic number, file version
-    // nodes with data
-    let mut node_data = Vec::<u8>::new();
-    let mut node_writer = std::io::Cursor::new(&mut node_data);
-    for node in &graph.nodes {
-        node_writer.write_all(&node_as_bytes(node)?)?;
-    }
-    writer.write_all(&(graph.nodes.len() as u32).to_le_bytes())?;
-    writer.write_all(&node_data)?;
-    // edges with data
-    let mut edge_data = Vec::<u8>::new();
-    let mut edge_writer = std::io::Cursor::new(&mut edge_data);
-    for edge in &graph.edges {
-        edge_writer.write_all(&edge_as_bytes(edge)?)?;
-    }
-    writer.write_all(&(graph.edges.len() as u32).to_le_bytes())?;
-    writer.write_all(&edge_data)?;
-    writer.flush()?;
+fn dump_binary(
+    graph: &graph::Graph,
+    pack_journeys: bool,
+    compress: bool,
+    skip_checksums: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = binary::encode(graph, skip_checksums, pack_journeys, compress)?;
+    std::fs::write("./graph.bin", bytes)?;
 
     let metas: Vec<MetaNode> = graph
         .nodes
@@ -224,6 +372,13 @@ fn dump_binary(graph: &graph::Graph) -> Result<(), Box<dyn std::error::Error>> {
             name: n.short_name.clone(),
         })
         .collect();
-    std::fs::write("nodes.json", serde_json::to_vec(&metas)?)?;
+    let meta_bytes = serde_json::to_vec(&metas)?;
+    if compress {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&meta_bytes)?;
+        std::fs::write("nodes.json.gz", encoder.finish()?)?;
+    } else {
+        std::fs::write("nodes.json", meta_bytes)?;
+    }
     Ok(())
 }
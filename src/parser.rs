@@ -333,7 +333,8 @@ impl NetexData {
     // In netex departure and arrival time are reqpresented as hh:mm:ss
     // seconds are mostly 00 anyway, so we only care about the minute of day
     // lets also assume times are represented as ascii chars
-    fn parse_minutes(value: &str) -> u16 {
+    // (GTFS times share the same "HH:MM:SS" layout, so NetexData::from_gtfs reuses this too)
+    pub(crate) fn parse_minutes(value: &str) -> u16 {
         const ASCII_ZERO: u16 = 48;
         let bytes = value.as_bytes();
         let mut result = 0_u16;
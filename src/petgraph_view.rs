@@ -0,0 +1,279 @@
+//! Adapts `Graph` to `petgraph`'s graph traits so callers can run Dijkstra,
+//! connected-components, betweenness centrality, etc. directly on the transit
+//! graph instead of the crate reimplementing each algorithm. Node indices are
+//! positions in `Graph.nodes`; edge weights come from a pluggable cost
+//! function over the underlying `Edge`.
+
+use petgraph::visit::{
+    Data, EdgeRef, GraphBase, IntoEdgeReferences, IntoEdges, IntoNodeIdentifiers,
+    NodeCompactIndexable, NodeCount, NodeIndexable, VisitMap, Visitable,
+};
+
+use crate::graph::{Edge, Graph};
+
+/// Computes the edge weight used for graph algorithms, or `None` to exclude
+/// the edge from the view entirely (e.g. no footpath and no timetable at
+/// all).
+pub type EdgeCost<'a> = dyn Fn(&Edge) -> Option<u32> + 'a;
+
+/// The smaller of the shortest observed journey duration in an edge's
+/// `Timetable` and its `walk_seconds` footpath, in seconds; whichever one
+/// exists when the edge has only one of them. A natural default cost for
+/// shortest-path queries over the transit graph.
+pub fn min_travel_time_cost(edge: &Edge) -> Option<u32> {
+    let shortest_journey = edge
+        .timetable
+        .journeys
+        .iter()
+        .map(|journey| {
+            let departure = u32::from(journey.departure);
+            let mut arrival = u32::from(journey.arrival);
+            if arrival < departure {
+                arrival += 24 * 60;
+            }
+            (arrival - departure) * 60
+        })
+        .min();
+    let walk = (edge.walk_seconds != u16::MAX).then_some(u32::from(edge.walk_seconds));
+    match (shortest_journey, walk) {
+        (Some(journey), Some(walk)) => Some(journey.min(walk)),
+        (Some(journey), None) => Some(journey),
+        (None, walk) => walk,
+    }
+}
+
+/// A view of a `Graph` as a `petgraph` graph.
+#[derive(Clone, Copy)]
+pub struct GraphView<'a> {
+    graph: &'a Graph,
+    cost: &'a EdgeCost<'a>,
+}
+
+impl<'a> GraphView<'a> {
+    pub fn new(graph: &'a Graph, cost: &'a EdgeCost<'a>) -> GraphView<'a> {
+        GraphView { graph, cost }
+    }
+}
+
+impl<'a> GraphBase for GraphView<'a> {
+    type EdgeId = usize;
+    type NodeId = usize;
+}
+
+impl<'a> Data for GraphView<'a> {
+    type NodeWeight = ();
+    type EdgeWeight = u32;
+}
+
+#[derive(Clone, Copy)]
+pub struct WeightedEdgeRef {
+    source: usize,
+    target: usize,
+    id: usize,
+    weight: u32,
+}
+
+impl EdgeRef for WeightedEdgeRef {
+    type NodeId = usize;
+    type EdgeId = usize;
+    type Weight = u32;
+
+    fn source(&self) -> usize {
+        self.source
+    }
+
+    fn target(&self) -> usize {
+        self.target
+    }
+
+    fn weight(&self) -> &u32 {
+        &self.weight
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl<'a> GraphView<'a> {
+    fn weighted_edges(self) -> impl Iterator<Item = WeightedEdgeRef> + 'a {
+        let cost = self.cost;
+        self.graph
+            .edges
+            .iter()
+            .enumerate()
+            .filter_map(move |(id, edge)| {
+                cost(edge).map(|weight| WeightedEdgeRef {
+                    source: edge.start_node,
+                    target: edge.end_node,
+                    id,
+                    weight,
+                })
+            })
+    }
+}
+
+impl<'a> IntoEdgeReferences for GraphView<'a> {
+    type EdgeRef = WeightedEdgeRef;
+    type EdgeReferences = Box<dyn Iterator<Item = WeightedEdgeRef> + 'a>;
+
+    fn edge_references(self) -> Self::EdgeReferences {
+        Box::new(self.weighted_edges())
+    }
+}
+
+impl<'a> IntoEdges for GraphView<'a> {
+    type Edges = std::vec::IntoIter<WeightedEdgeRef>;
+
+    fn edges(self, a: usize) -> Self::Edges {
+        self.weighted_edges()
+            .filter(|edge| edge.source == a)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a> IntoNodeIdentifiers for GraphView<'a> {
+    type NodeIdentifiers = std::ops::Range<usize>;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        0..self.graph.nodes.len()
+    }
+}
+
+impl<'a> NodeIndexable for GraphView<'a> {
+    fn node_bound(&self) -> usize {
+        self.graph.nodes.len()
+    }
+
+    fn to_index(&self, a: usize) -> usize {
+        a
+    }
+
+    fn from_index(&self, i: usize) -> usize {
+        i
+    }
+}
+
+impl<'a> NodeCount for GraphView<'a> {
+    fn node_count(&self) -> usize {
+        self.graph.nodes.len()
+    }
+}
+
+// Node ids are exactly `0..node_count()`, as required by e.g.
+// `petgraph::algo::connected_components`.
+impl<'a> NodeCompactIndexable for GraphView<'a> {}
+
+/// A plain `Vec<bool>`-backed `VisitMap`, avoiding a dependency on
+/// `fixedbitset` for a single bit per node.
+pub struct NodeVisitMap(Vec<bool>);
+
+impl VisitMap<usize> for NodeVisitMap {
+    fn visit(&mut self, a: usize) -> bool {
+        !std::mem::replace(&mut self.0[a], true)
+    }
+
+    fn is_visited(&self, a: &usize) -> bool {
+        self.0[*a]
+    }
+}
+
+impl<'a> Visitable for GraphView<'a> {
+    type Map = NodeVisitMap;
+
+    fn visit_map(&self) -> Self::Map {
+        NodeVisitMap(vec![false; self.graph.nodes.len()])
+    }
+
+    fn reset_map(&self, map: &mut Self::Map) {
+        map.0.clear();
+        map.0.resize(self.graph.nodes.len(), false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Journey, Node, OperatingPeriod, Timetable};
+
+    fn node(id: u64) -> Node {
+        Node {
+            id,
+            short_name: id.to_string(),
+            long: 0.0,
+            lat: 0.0,
+        }
+    }
+
+    #[test]
+    fn dijkstra_finds_the_cheaper_of_two_routes() {
+        // 0 -> 1 -> 2 is a short walk; 0 -> 2 direct is a much longer one.
+        // `petgraph::algo::dijkstra`, driven by `min_travel_time_cost`, must
+        // prefer the two-hop route.
+        let nodes = vec![node(1), node(2), node(3)];
+        let edges = vec![
+            Edge {
+                start_node: 0,
+                end_node: 1,
+                walk_seconds: 60,
+                timetable: Timetable {
+                    journeys: vec![],
+                    periods: vec![],
+                },
+            },
+            Edge {
+                start_node: 1,
+                end_node: 2,
+                walk_seconds: 60,
+                timetable: Timetable {
+                    journeys: vec![],
+                    periods: vec![],
+                },
+            },
+            Edge {
+                start_node: 0,
+                end_node: 2,
+                walk_seconds: 1000,
+                timetable: Timetable {
+                    journeys: vec![],
+                    periods: vec![],
+                },
+            },
+        ];
+        let graph = Graph::new(nodes, edges);
+        let cost: &EdgeCost = &min_travel_time_cost;
+        let view = GraphView::new(&graph, cost);
+
+        let costs = petgraph::algo::dijkstra(view, 0, Some(2), |edge| *edge.weight());
+
+        assert_eq!(costs.get(&2), Some(&120));
+    }
+
+    #[test]
+    fn min_travel_time_cost_prefers_the_faster_of_journey_and_walk() {
+        let edge = Edge {
+            start_node: 0,
+            end_node: 1,
+            walk_seconds: 30,
+            timetable: Timetable {
+                journeys: vec![Journey {
+                    departure: 480,
+                    arrival: 490,
+                    transport_mode: "bus".to_owned(),
+                    operating_period: 0,
+                    line: "1".to_owned(),
+                    controller: "Acme".to_owned(),
+                }],
+                periods: vec![OperatingPeriod {
+                    from: 220_101,
+                    to: 220_101,
+                    valid_day_bits: String::new(),
+                    valid_day: vec![0b0000_0001],
+                }],
+            },
+        };
+
+        assert_eq!(min_travel_time_cost(&edge), Some(30));
+    }
+}
@@ -0,0 +1,127 @@
+//! Named regions used to filter stops by coordinate, loaded from a TOML
+//! config file. Replaces the previously hardcoded Germany bounding box with
+//! a reusable filter that works for any region without recompiling.
+
+use geo::{Contains, Coord, LineString, Point, Polygon};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RegionFilter {
+    #[serde(rename = "region")]
+    regions: Vec<Region>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Region {
+    #[allow(dead_code)]
+    name: String,
+    #[serde(flatten)]
+    shape: RegionShape,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum RegionShape {
+    BoundingBox {
+        min_lat: f32,
+        max_lat: f32,
+        min_long: f32,
+        max_long: f32,
+    },
+    Polygon {
+        // Ring of [long, lat] pairs; open rings are closed automatically
+        // before the containment check.
+        polygon: Vec<[f32; 2]>,
+    },
+}
+
+impl RegionShape {
+    fn contains(&self, long: f32, lat: f32) -> bool {
+        match self {
+            RegionShape::BoundingBox {
+                min_lat,
+                max_lat,
+                min_long,
+                max_long,
+            } => long > *min_long && long < *max_long && lat > *min_lat && lat < *max_lat,
+            RegionShape::Polygon { polygon } => {
+                let mut coords: Vec<Coord<f32>> = polygon
+                    .iter()
+                    .map(|[long, lat]| Coord { x: *long, y: *lat })
+                    .collect();
+                if coords.first() != coords.last() {
+                    if let Some(first) = coords.first().copied() {
+                        coords.push(first);
+                    }
+                }
+                let ring: LineString<f32> = coords.into();
+                Polygon::new(ring, vec![]).contains(&Point::new(long, lat))
+            }
+        }
+    }
+}
+
+impl RegionFilter {
+    /// Parses a `RegionFilter` from the contents of a TOML config file
+    /// describing one or more `[[region]]` entries.
+    pub fn from_toml(contents: &str) -> Result<RegionFilter, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Returns true if `(long, lat)` falls inside any configured region.
+    pub fn contains(&self, long: f32, lat: f32) -> bool {
+        self.regions.iter().any(|region| region.shape.contains(long, lat))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounding_box_contains_points_inside_but_not_outside() {
+        let filter = RegionFilter::from_toml(
+            r#"
+            [[region]]
+            name = "box"
+            min_lat = 47.0
+            max_lat = 55.0
+            min_long = 6.0
+            max_long = 15.0
+            "#,
+        )
+        .unwrap();
+
+        assert!(filter.contains(10.0, 50.0));
+        assert!(!filter.contains(2.0, 48.0));
+    }
+
+    #[test]
+    fn polygon_contains_points_inside_but_not_outside() {
+        let filter = RegionFilter::from_toml(
+            r#"
+            [[region]]
+            name = "triangle"
+            polygon = [[0.0, 0.0], [4.0, 0.0], [0.0, 4.0], [0.0, 0.0]]
+            "#,
+        )
+        .unwrap();
+
+        assert!(filter.contains(1.0, 1.0));
+        assert!(!filter.contains(3.0, 3.0));
+    }
+
+    #[test]
+    fn polygon_with_open_ring_is_closed_before_containment_check() {
+        let filter = RegionFilter::from_toml(
+            r#"
+            [[region]]
+            name = "triangle"
+            polygon = [[0.0, 0.0], [4.0, 0.0], [0.0, 4.0]]
+            "#,
+        )
+        .unwrap();
+
+        assert!(filter.contains(1.0, 1.0));
+        assert!(!filter.contains(3.0, 3.0));
+    }
+}
@@ -0,0 +1,781 @@
+//! Earliest-arrival journey planning on top of `Graph`, using the Connection
+//! Scan Algorithm (CSA): every `Journey` in every `Edge` is flattened into a
+//! single sorted list of connections, which is then scanned once per query.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::date;
+use crate::graph::{great_circle_distance, Graph, OperatingPeriod};
+use crate::petgraph_view::min_travel_time_cost;
+
+// The crate's own plausible-speed cap (see `Graph::filter_journeys`), reused
+// here as the upper bound for the A* heuristic's assumed travel speed.
+const MAX_TRANSIT_SPEED_KMH: f32 = 325.0;
+
+/// A single directed hop between two nodes at fixed clock times, flattened out
+/// of an edge's `Timetable`. Footpaths (`Edge.walk_seconds`) are kept separate
+/// and are applied as a relaxation step instead of being scanned as connections.
+#[derive(Clone, Copy, Debug)]
+struct Connection {
+    edge: usize,
+    journey: usize,
+    dep_node: usize,
+    arr_node: usize,
+    departure: u16,
+    arrival: u16,
+}
+
+/// One leg of a reconstructed itinerary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Leg {
+    /// Boarding `edge`'s `journey`-th journey.
+    Connection {
+        edge: usize,
+        journey: usize,
+        departure: u16,
+        arrival: u16,
+    },
+    /// Walking along a footpath edge.
+    Walk { edge: usize, arrival: u16 },
+}
+
+impl Leg {
+    fn arrival(&self) -> u16 {
+        match self {
+            Leg::Connection { arrival, .. } | Leg::Walk { arrival, .. } => *arrival,
+        }
+    }
+}
+
+/// Result of an earliest-arrival query.
+#[derive(Clone, Debug)]
+pub struct EarliestArrival {
+    pub arrival: u16,
+    pub legs: Vec<Leg>,
+}
+
+impl Graph {
+    /// Find the earliest time one can reach `to` from `from`, departing no
+    /// earlier than `departure` (minutes after midnight) on `day` (a
+    /// `YYMMDD`-encoded service date), using the Connection Scan Algorithm.
+    ///
+    /// Follows the midnight wraparound convention already used in
+    /// `filter_journeys`: a connection whose arrival is earlier than its
+    /// departure is assumed to arrive on the following day.
+    pub fn earliest_arrival(
+        &self,
+        from: usize,
+        to: usize,
+        departure: u16,
+        day: u32,
+    ) -> Option<EarliestArrival> {
+        let mut connections = self.flatten_connections();
+        connections.sort_unstable_by_key(|c| c.departure);
+
+        let mut arrival = vec![u16::MAX; self.nodes.len()];
+        let mut predecessor = HashMap::<usize, Leg>::new();
+        arrival[from] = departure;
+        self.relax_footpaths(from, &mut arrival, &mut predecessor);
+
+        for connection in &connections {
+            if connection.departure > arrival[to] {
+                break;
+            }
+            if arrival[connection.dep_node] > connection.departure {
+                continue;
+            }
+            if !self.connection_runs_on(connection, day) {
+                continue;
+            }
+            if connection.arrival >= arrival[connection.arr_node] {
+                continue;
+            }
+            arrival[connection.arr_node] = connection.arrival;
+            predecessor.insert(
+                connection.arr_node,
+                Leg::Connection {
+                    edge: connection.edge,
+                    journey: connection.journey,
+                    departure: connection.departure,
+                    arrival: connection.arrival,
+                },
+            );
+            self.relax_footpaths(connection.arr_node, &mut arrival, &mut predecessor);
+        }
+
+        if arrival[to] == u16::MAX {
+            return None;
+        }
+        Some(EarliestArrival {
+            arrival: arrival[to],
+            legs: self.reconstruct_path(from, to, &predecessor),
+        })
+    }
+
+    // An edge's journeys and its footpath (`walk_seconds`) are independent:
+    // `Graph::update_walk` can attach a finite `walk_seconds` to an edge that
+    // already carries a `Timetable`, so both must be usable on the same
+    // edge. Don't filter edges by `walk_seconds` here — an edge with no
+    // journeys simply contributes nothing via `flat_map`.
+    fn flatten_connections(&self) -> Vec<Connection> {
+        self.edges
+            .iter()
+            .enumerate()
+            .flat_map(|(edge_idx, edge)| {
+                edge.timetable
+                    .journeys
+                    .iter()
+                    .enumerate()
+                    .map(move |(journey_idx, journey)| Connection {
+                        edge: edge_idx,
+                        journey: journey_idx,
+                        dep_node: edge.start_node,
+                        arr_node: edge.end_node,
+                        departure: journey.departure,
+                        arrival: if journey.arrival < journey.departure {
+                            journey.arrival.saturating_add(24 * 60)
+                        } else {
+                            journey.arrival
+                        },
+                    })
+            })
+            .collect()
+    }
+
+    fn connection_runs_on(&self, connection: &Connection, day: u32) -> bool {
+        let edge = &self.edges[connection.edge];
+        let period_idx = edge.timetable.journeys[connection.journey].operating_period;
+        let Some(period) = edge.timetable.periods.get(period_idx) else {
+            return false;
+        };
+        Self::period_valid_on(period, day)
+    }
+
+    fn period_valid_on(period: &OperatingPeriod, day: u32) -> bool {
+        let offset = date::days_between(period.from, day);
+        if offset < 0 || day > period.to {
+            return false;
+        }
+        let Ok(offset) = usize::try_from(offset) else {
+            return false;
+        };
+        let Some(byte) = period.valid_day.get(offset / 8) else {
+            return false;
+        };
+        (byte >> (offset % 8)) & 1 == 1
+    }
+
+    fn relax_footpaths(
+        &self,
+        from: usize,
+        arrival: &mut [u16],
+        predecessor: &mut HashMap<usize, Leg>,
+    ) {
+        let mut frontier = vec![from];
+        while let Some(node) = frontier.pop() {
+            for (edge_idx, edge) in self.edges.iter().enumerate() {
+                if edge.start_node != node || edge.walk_seconds == u16::MAX {
+                    continue;
+                }
+                // `arrival` is minutes-of-day; `walk_seconds` is, as the name
+                // says, seconds, so it must be rounded up to minutes before
+                // being combined with `arrival` (round up so a fractional
+                // minute of walking can never make a connection look
+                // reachable that a traveller couldn't actually make).
+                let candidate = arrival[node].saturating_add(edge.walk_seconds.div_ceil(60));
+                if candidate < arrival[edge.end_node] {
+                    arrival[edge.end_node] = candidate;
+                    predecessor.insert(
+                        edge.end_node,
+                        Leg::Walk {
+                            edge: edge_idx,
+                            arrival: candidate,
+                        },
+                    );
+                    frontier.push(edge.end_node);
+                }
+            }
+        }
+    }
+
+    fn reconstruct_path(
+        &self,
+        from: usize,
+        to: usize,
+        predecessor: &HashMap<usize, Leg>,
+    ) -> Vec<Leg> {
+        let mut legs = Vec::new();
+        let mut node = to;
+        while node != from {
+            let Some(leg) = predecessor.get(&node) else {
+                break;
+            };
+            legs.push(*leg);
+            node = match leg {
+                Leg::Connection { edge, .. } | Leg::Walk { edge, .. } => {
+                    self.edges[*edge].start_node
+                }
+            };
+        }
+        legs.reverse();
+        legs
+    }
+}
+
+/// Routing mode for `Graph::search_path`, trading optimality for speed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Unweighted breadth-first search: finds a path in the fewest hops,
+    /// ignoring travel time entirely.
+    Bfs,
+    /// Best-first search driven purely by the geographic heuristic, ignoring
+    /// accumulated cost. Fast, but not guaranteed optimal.
+    Greedy,
+    /// A* using `cost-so-far + geographic heuristic`, admissible (and
+    /// therefore optimal) as long as no edge is faster than
+    /// `MAX_TRANSIT_SPEED_KMH`.
+    AStar,
+}
+
+#[derive(Clone, Copy)]
+struct Candidate {
+    node: usize,
+    cost: u32,
+}
+
+/// Result of a static `Graph::search_path` query: the node path from source
+/// to target and its total cost. For `SearchMode::Greedy` and `SearchMode::
+/// AStar` this is the estimated travel time in seconds; for `SearchMode::
+/// Bfs`, which ignores travel time, it's the hop count instead.
+#[derive(Clone, Debug)]
+pub struct SearchPath {
+    pub nodes: Vec<usize>,
+    pub cost_seconds: u32,
+}
+
+impl Graph {
+    /// Finds a path from `from` to `to` over footpath-and-minimum-journey
+    /// costs (see `crate::petgraph_view::min_travel_time_cost`), using
+    /// `mode` to trade optimality for speed.
+    ///
+    /// When `beam_width` is set, the open set is pruned to the `beam_width`
+    /// most promising nodes after every expansion, bounding memory and time
+    /// on very large graphs at the cost of completeness.
+    pub fn search_path(
+        &self,
+        from: usize,
+        to: usize,
+        mode: SearchMode,
+        beam_width: Option<usize>,
+    ) -> Option<SearchPath> {
+        let heuristic = |node: usize| -> u32 {
+            if mode == SearchMode::Bfs {
+                return 0;
+            }
+            let a = &self.nodes[node];
+            let b = &self.nodes[to];
+            let distance_km = great_circle_distance((a.long, a.lat), (b.long, b.lat));
+            ((distance_km / MAX_TRANSIT_SPEED_KMH) * 3600.0) as u32
+        };
+        let score = |candidate: &Candidate| -> u32 {
+            match mode {
+                SearchMode::Bfs | SearchMode::AStar => {
+                    candidate.cost.saturating_add(heuristic(candidate.node))
+                }
+                SearchMode::Greedy => heuristic(candidate.node),
+            }
+        };
+
+        let mut open = vec![Candidate { node: from, cost: 0 }];
+        let mut best_cost = HashMap::from([(from, 0_u32)]);
+        let mut predecessor = HashMap::<usize, usize>::new();
+        let mut closed = HashSet::new();
+
+        while !open.is_empty() {
+            open.sort_by_key(score);
+            if let Some(width) = beam_width {
+                // A width of 0 would empty `open` entirely and make the
+                // `open.remove(0)` below panic, even for a trivial
+                // `from == to` query, so treat it as "keep at least one".
+                open.truncate(width.max(1));
+            }
+            let current = open.remove(0);
+            if current.node == to {
+                return Some(SearchPath {
+                    nodes: Self::reconstruct_node_path(from, to, &predecessor),
+                    cost_seconds: current.cost,
+                });
+            }
+            if !closed.insert(current.node) {
+                continue;
+            }
+            for edge in self.edges.iter().filter(|edge| edge.start_node == current.node) {
+                let Some(travel_cost) = min_travel_time_cost(edge) else {
+                    continue;
+                };
+                // Bfs ignores travel time entirely and counts hops instead,
+                // so it finds the fewest-hops path rather than the fastest.
+                let weight = if mode == SearchMode::Bfs { 1 } else { travel_cost };
+                let neighbor_cost = current.cost.saturating_add(weight);
+                let improved = best_cost
+                    .get(&edge.end_node)
+                    .map_or(true, |&known| neighbor_cost < known);
+                if improved {
+                    best_cost.insert(edge.end_node, neighbor_cost);
+                    predecessor.insert(edge.end_node, current.node);
+                    open.push(Candidate {
+                        node: edge.end_node,
+                        cost: neighbor_cost,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn reconstruct_node_path(from: usize, to: usize, predecessor: &HashMap<usize, usize>) -> Vec<usize> {
+        let mut nodes = vec![to];
+        let mut node = to;
+        while node != from {
+            let Some(&prev) = predecessor.get(&node) else {
+                break;
+            };
+            nodes.push(prev);
+            node = prev;
+        }
+        nodes.reverse();
+        nodes
+    }
+}
+
+/// A single Pareto-optimal journey from a `Graph::pareto_journeys` query,
+/// trading arrival time against number of transfers.
+#[derive(Clone, Debug)]
+pub struct ParetoJourney {
+    pub arrival: u16,
+    pub transfers: u16,
+    pub legs: Vec<Leg>,
+}
+
+// One entry in the round-based label arena: the (arrival, transfers) pair
+// reached at `node`, the `Journey.line`/`Journey.controller` last boarded
+// (used to tell a transfer from staying on the same vehicle), and the leg and
+// predecessor label that produced it.
+struct ParetoLabel {
+    node: usize,
+    arrival: u16,
+    transfers: u16,
+    last_line: Option<(String, String)>,
+    leg: Option<Leg>,
+    predecessor: Option<usize>,
+}
+
+impl Graph {
+    /// Round-based (RAPTOR-style) multi-criteria search returning every
+    /// Pareto-optimal `(arrival, transfers)` journey from `from` to `to`,
+    /// departing no earlier than `departure` on `day`.
+    ///
+    /// Round `k` relaxes every `Journey` reachable from a node whose label
+    /// improved in round `k - 1`, so round `k` corresponds to at most `k`
+    /// vehicle boardings. A boarding only counts as a transfer when the
+    /// previous leg ran under a different `Journey.line`/`Journey.controller`
+    /// pair, so staying on the same line across edges is free.
+    pub fn pareto_journeys(
+        &self,
+        from: usize,
+        to: usize,
+        departure: u16,
+        day: u32,
+    ) -> Vec<ParetoJourney> {
+        let connections = self.flatten_connections();
+
+        let mut arena = Vec::<ParetoLabel>::new();
+        let mut fronts = HashMap::<usize, Vec<usize>>::new();
+        arena.push(ParetoLabel {
+            node: from,
+            arrival: departure,
+            transfers: 0,
+            last_line: None,
+            leg: None,
+            predecessor: None,
+        });
+        fronts.insert(from, vec![0]);
+
+        let mut marked = HashSet::from([from]);
+        self.relax_footpaths_pareto(from, &mut arena, &mut fronts, &mut marked);
+
+        loop {
+            if marked.is_empty() {
+                break;
+            }
+            let mut candidates = Vec::<ParetoLabel>::new();
+            for &node in &marked {
+                let label_ids = fronts.get(&node).cloned().unwrap_or_default();
+                for label_id in label_ids {
+                    let label_arrival = arena[label_id].arrival;
+                    let last_line = arena[label_id].last_line.clone();
+                    for connection in &connections {
+                        if connection.dep_node != node || connection.departure < label_arrival {
+                            continue;
+                        }
+                        if !self.connection_runs_on(connection, day) {
+                            continue;
+                        }
+                        let edge = &self.edges[connection.edge];
+                        let journey = &edge.timetable.journeys[connection.journey];
+                        let this_line = (journey.line.clone(), journey.controller.clone());
+                        let transfers = match &last_line {
+                            Some(prev) if *prev == this_line => arena[label_id].transfers,
+                            None => arena[label_id].transfers,
+                            Some(_) => arena[label_id].transfers + 1,
+                        };
+                        candidates.push(ParetoLabel {
+                            node: connection.arr_node,
+                            arrival: connection.arrival,
+                            transfers,
+                            last_line: Some(this_line),
+                            leg: Some(Leg::Connection {
+                                edge: connection.edge,
+                                journey: connection.journey,
+                                departure: connection.departure,
+                                arrival: connection.arrival,
+                            }),
+                            predecessor: Some(label_id),
+                        });
+                    }
+                }
+            }
+
+            let mut newly_marked = HashSet::new();
+            for candidate in candidates {
+                let node = candidate.node;
+                arena.push(candidate);
+                let id = arena.len() - 1;
+                if Self::insert_if_nondominated(&arena, fronts.entry(node).or_default(), id) {
+                    newly_marked.insert(node);
+                } else {
+                    arena.pop();
+                }
+            }
+            for node in newly_marked.clone() {
+                self.relax_footpaths_pareto(node, &mut arena, &mut fronts, &mut newly_marked);
+            }
+            marked = newly_marked;
+        }
+
+        fronts
+            .get(&to)
+            .map(|ids| {
+                ids.iter()
+                    .map(|&id| ParetoJourney {
+                        arrival: arena[id].arrival,
+                        transfers: arena[id].transfers,
+                        legs: Self::reconstruct_pareto_path(&arena, id),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Propagates footpaths out of every label currently on `node`'s Pareto
+    // front. Walking doesn't count as a boarding, so it preserves both the
+    // transfer count and the last-boarded line.
+    fn relax_footpaths_pareto(
+        &self,
+        node: usize,
+        arena: &mut Vec<ParetoLabel>,
+        fronts: &mut HashMap<usize, Vec<usize>>,
+        marked: &mut HashSet<usize>,
+    ) {
+        let mut frontier = vec![node];
+        while let Some(node) = frontier.pop() {
+            let label_ids = fronts.get(&node).cloned().unwrap_or_default();
+            for label_id in label_ids {
+                for (edge_idx, edge) in self.edges.iter().enumerate() {
+                    if edge.start_node != node || edge.walk_seconds == u16::MAX {
+                        continue;
+                    }
+                    let label = &arena[label_id];
+                    // Same minutes-vs-seconds conversion as `relax_footpaths`.
+                    let arrival = label.arrival.saturating_add(edge.walk_seconds.div_ceil(60));
+                    let candidate = ParetoLabel {
+                        node: edge.end_node,
+                        arrival,
+                        transfers: label.transfers,
+                        last_line: label.last_line.clone(),
+                        leg: Some(Leg::Walk {
+                            edge: edge_idx,
+                            arrival,
+                        }),
+                        predecessor: Some(label_id),
+                    };
+                    let target = edge.end_node;
+                    arena.push(candidate);
+                    let id = arena.len() - 1;
+                    if Self::insert_if_nondominated(arena, fronts.entry(target).or_default(), id) {
+                        marked.insert(target);
+                        frontier.push(target);
+                    } else {
+                        arena.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    // Inserts `candidate` into `front` if no existing label there dominates
+    // it (arrival and transfers both at least as good), discarding any
+    // existing labels the candidate itself dominates.
+    fn insert_if_nondominated(arena: &[ParetoLabel], front: &mut Vec<usize>, candidate: usize) -> bool {
+        let (arrival, transfers) = (arena[candidate].arrival, arena[candidate].transfers);
+        if front
+            .iter()
+            .any(|&id| arena[id].arrival <= arrival && arena[id].transfers <= transfers)
+        {
+            return false;
+        }
+        front.retain(|&id| !(arrival <= arena[id].arrival && transfers <= arena[id].transfers));
+        front.push(candidate);
+        true
+    }
+
+    fn reconstruct_pareto_path(arena: &[ParetoLabel], label_id: usize) -> Vec<Leg> {
+        let mut legs = Vec::new();
+        let mut current = Some(label_id);
+        while let Some(id) = current {
+            if let Some(leg) = arena[id].leg {
+                legs.push(leg);
+            }
+            current = arena[id].predecessor;
+        }
+        legs.reverse();
+        legs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Edge, Journey, Node, Timetable};
+
+    fn node(id: u64) -> Node {
+        Node {
+            id,
+            short_name: id.to_string(),
+            long: 0.0,
+            lat: 0.0,
+        }
+    }
+
+    #[test]
+    fn earliest_arrival_prefers_a_journey_over_a_slower_walk_on_the_same_edge() {
+        // A single edge with both a footpath and a scheduled journey: the
+        // journey is faster, so it must win even though `Graph::update_walk`
+        // also stamped this edge with a `walk_seconds`.
+        let nodes = vec![node(1), node(2)];
+        let edges = vec![Edge {
+            start_node: 0,
+            end_node: 1,
+            walk_seconds: 1800,
+            timetable: Timetable {
+                journeys: vec![Journey {
+                    departure: 480,
+                    arrival: 490,
+                    transport_mode: "bus".to_owned(),
+                    operating_period: 0,
+                    line: "1".to_owned(),
+                    controller: "Acme".to_owned(),
+                }],
+                periods: vec![OperatingPeriod {
+                    from: 220_101,
+                    to: 220_101,
+                    valid_day_bits: String::new(),
+                    valid_day: vec![0b0000_0001],
+                }],
+            },
+        }];
+        let graph = Graph::new(nodes, edges);
+
+        let result = graph.earliest_arrival(0, 1, 480, 220_101).unwrap();
+
+        assert_eq!(result.arrival, 490);
+        assert!(matches!(result.legs.last(), Some(Leg::Connection { .. })));
+    }
+
+    #[test]
+    fn relax_footpaths_converts_walk_seconds_to_minutes() {
+        // A footpath-only edge with a walk time that isn't a whole number of
+        // minutes: 90 seconds must land at arrival + 2 (rounded up), not
+        // arrival + 90 (the bug this guards against: treating `walk_seconds`
+        // as if it were already minutes).
+        let nodes = vec![node(1), node(2)];
+        let edges = vec![Edge {
+            start_node: 0,
+            end_node: 1,
+            walk_seconds: 90,
+            timetable: Timetable {
+                journeys: vec![],
+                periods: vec![],
+            },
+        }];
+        let graph = Graph::new(nodes, edges);
+
+        let result = graph.earliest_arrival(0, 1, 480, 220_101).unwrap();
+
+        assert_eq!(result.arrival, 482);
+        assert!(matches!(result.legs.last(), Some(Leg::Walk { .. })));
+    }
+
+    #[test]
+    fn pareto_journeys_offers_both_the_fast_transfer_and_the_slow_direct_option() {
+        // node 0 -> node 1 -> node 2 by two short journeys (one transfer), and
+        // node 0 -> node 2 directly by a slower journey (no transfer). Neither
+        // dominates the other, so both must survive onto node 2's front.
+        let nodes = vec![node(1), node(2), node(3)];
+        let edges = vec![
+            Edge {
+                start_node: 0,
+                end_node: 1,
+                walk_seconds: u16::MAX,
+                timetable: Timetable {
+                    journeys: vec![Journey {
+                        departure: 480,
+                        arrival: 490,
+                        transport_mode: "bus".to_owned(),
+                        operating_period: 0,
+                        line: "1".to_owned(),
+                        controller: "Acme".to_owned(),
+                    }],
+                    periods: vec![OperatingPeriod {
+                        from: 220_101,
+                        to: 220_101,
+                        valid_day_bits: String::new(),
+                        valid_day: vec![0b0000_0001],
+                    }],
+                },
+            },
+            Edge {
+                start_node: 1,
+                end_node: 2,
+                walk_seconds: u16::MAX,
+                timetable: Timetable {
+                    journeys: vec![Journey {
+                        departure: 495,
+                        arrival: 500,
+                        transport_mode: "bus".to_owned(),
+                        operating_period: 0,
+                        line: "2".to_owned(),
+                        controller: "Acme".to_owned(),
+                    }],
+                    periods: vec![OperatingPeriod {
+                        from: 220_101,
+                        to: 220_101,
+                        valid_day_bits: String::new(),
+                        valid_day: vec![0b0000_0001],
+                    }],
+                },
+            },
+            Edge {
+                start_node: 0,
+                end_node: 2,
+                walk_seconds: u16::MAX,
+                timetable: Timetable {
+                    journeys: vec![Journey {
+                        departure: 480,
+                        arrival: 520,
+                        transport_mode: "bus".to_owned(),
+                        operating_period: 0,
+                        line: "3".to_owned(),
+                        controller: "Acme".to_owned(),
+                    }],
+                    periods: vec![OperatingPeriod {
+                        from: 220_101,
+                        to: 220_101,
+                        valid_day_bits: String::new(),
+                        valid_day: vec![0b0000_0001],
+                    }],
+                },
+            },
+        ];
+        let graph = Graph::new(nodes, edges);
+
+        let mut journeys = graph.pareto_journeys(0, 2, 480, 220_101);
+        journeys.sort_by_key(|journey| journey.arrival);
+
+        assert_eq!(journeys.len(), 2);
+        assert_eq!(journeys[0].arrival, 500);
+        assert_eq!(journeys[0].transfers, 1);
+        assert_eq!(journeys[1].arrival, 520);
+        assert_eq!(journeys[1].transfers, 0);
+    }
+
+    fn three_node_graph_with_a_shortcut() -> Graph {
+        // 0 -> 1 -> 2 is two cheap hops; 0 -> 2 direct is one expensive hop.
+        let nodes = vec![node(1), node(2), node(3)];
+        let edges = vec![
+            Edge {
+                start_node: 0,
+                end_node: 1,
+                walk_seconds: 60,
+                timetable: Timetable::default(),
+            },
+            Edge {
+                start_node: 1,
+                end_node: 2,
+                walk_seconds: 60,
+                timetable: Timetable::default(),
+            },
+            Edge {
+                start_node: 0,
+                end_node: 2,
+                walk_seconds: 1000,
+                timetable: Timetable::default(),
+            },
+        ];
+        Graph::new(nodes, edges)
+    }
+
+    #[test]
+    fn search_path_bfs_counts_hops_not_travel_time() {
+        let graph = three_node_graph_with_a_shortcut();
+
+        let path = graph.search_path(0, 2, SearchMode::Bfs, None).unwrap();
+
+        // Bfs ignores travel time, so it returns the fewest-hops path (0-2
+        // direct, 1 hop) even though 0-1-2 is far cheaper in travel time.
+        assert_eq!(path.nodes, vec![0, 2]);
+        assert_eq!(path.cost_seconds, 1);
+    }
+
+    #[test]
+    fn search_path_astar_finds_the_cheapest_route() {
+        let graph = three_node_graph_with_a_shortcut();
+
+        let path = graph.search_path(0, 2, SearchMode::AStar, None).unwrap();
+
+        assert_eq!(path.nodes, vec![0, 1, 2]);
+        assert_eq!(path.cost_seconds, 120);
+    }
+
+    #[test]
+    fn search_path_greedy_reaches_the_target() {
+        let graph = three_node_graph_with_a_shortcut();
+
+        let path = graph.search_path(0, 2, SearchMode::Greedy, None).unwrap();
+
+        assert_eq!(*path.nodes.first().unwrap(), 0);
+        assert_eq!(*path.nodes.last().unwrap(), 2);
+    }
+
+    #[test]
+    fn search_path_with_beam_width_zero_does_not_panic() {
+        let graph = three_node_graph_with_a_shortcut();
+
+        let path = graph
+            .search_path(0, 2, SearchMode::AStar, Some(0))
+            .unwrap();
+
+        assert_eq!(*path.nodes.first().unwrap(), 0);
+        assert_eq!(*path.nodes.last().unwrap(), 2);
+    }
+}